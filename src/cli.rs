@@ -0,0 +1,178 @@
+use crate::config::{self, InitMassDist};
+
+#[derive(Clone, Debug)]
+pub struct CliOptions {
+    pub seed: Option<u64>,
+    pub physics_only: bool,
+    pub init_mass_dist: InitMassDist,
+    pub event_log: Option<String>,
+    // "-" なら stderr。TUI が stdout を使うので stdout には出さない
+    pub telemetry: Option<String>,
+    pub telemetry_interval_ms: u64,
+    // `tick,text,mass[,x,y]` の予約ファイル
+    pub schedule: Option<String>,
+    // 起動時に AUTOSAVE_PATH があれば復元する
+    pub resume: bool,
+}
+
+impl Default for CliOptions {
+    fn default() -> Self {
+        Self {
+            seed: None,
+            physics_only: false,
+            init_mass_dist: config::INIT_MASS_DIST,
+            event_log: None,
+            telemetry: None,
+            telemetry_interval_ms: config::TELEMETRY_INTERVAL_MS,
+            schedule: None,
+            resume: false,
+        }
+    }
+}
+
+impl CliOptions {
+    pub fn parse<I: IntoIterator<Item = String>>(args: I) -> Result<Self, String> {
+        let mut options = Self::default();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--seed" => {
+                    let value = args.next().ok_or("--seed requires a value")?;
+                    let seed = value
+                        .parse()
+                        .map_err(|_| format!("invalid --seed value: {}", value))?;
+                    options.seed = Some(seed);
+                }
+                "--physics-only" => options.physics_only = true,
+                "--resume" => options.resume = true,
+                "--init-mass-dist" => {
+                    let value = args.next().ok_or("--init-mass-dist requires a value")?;
+                    options.init_mass_dist = parse_init_mass_dist(&value)?;
+                }
+                "--event-log" => {
+                    let path = args.next().ok_or("--event-log requires a path")?;
+                    options.event_log = Some(path);
+                }
+                "--telemetry" => {
+                    let path = args.next().ok_or("--telemetry requires a path")?;
+                    options.telemetry = Some(path);
+                }
+                "--telemetry-interval" => {
+                    let value = args.next().ok_or("--telemetry-interval requires a value")?;
+                    options.telemetry_interval_ms = value
+                        .parse()
+                        .map_err(|_| format!("invalid --telemetry-interval value: {}", value))?;
+                }
+                "--schedule" => {
+                    let path = args.next().ok_or("--schedule requires a path")?;
+                    options.schedule = Some(path);
+                }
+                other => return Err(format!("unknown argument: {}", other)),
+            }
+        }
+        Ok(options)
+    }
+}
+
+// "fixed" | "power-law" | "power-law:<alpha>"
+fn parse_init_mass_dist(value: &str) -> Result<InitMassDist, String> {
+    let (name, param) = match value.split_once(':') {
+        Some((name, param)) => (name, Some(param)),
+        None => (value, None),
+    };
+    match (name, param) {
+        ("fixed", None) => Ok(InitMassDist::Fixed),
+        ("power-law", None) => Ok(InitMassDist::PowerLaw {
+            alpha: config::INIT_POWER_LAW_ALPHA,
+        }),
+        ("power-law", Some(alpha)) => alpha
+            .parse()
+            .map(|alpha| InitMassDist::PowerLaw { alpha })
+            .map_err(|_| format!("invalid power-law alpha: {}", alpha)),
+        _ => Err(format!("unknown --init-mass-dist value: {}", value)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    mod parse {
+        use super::*;
+
+        #[test]
+        fn no_arguments_gives_defaults() {
+            let options = CliOptions::parse(args(&[])).unwrap();
+            assert!(options.seed.is_none());
+            assert!(!options.physics_only);
+        }
+
+        #[test]
+        fn parses_seed() {
+            let options = CliOptions::parse(args(&["--seed", "42"])).unwrap();
+            assert_eq!(options.seed, Some(42));
+        }
+
+        #[test]
+        fn parses_physics_only_flag() {
+            let options = CliOptions::parse(args(&["--physics-only", "--seed", "1"])).unwrap();
+            assert!(options.physics_only);
+            assert_eq!(options.seed, Some(1));
+        }
+
+        #[test]
+        fn parses_init_mass_dist() {
+            let options = CliOptions::parse(args(&["--init-mass-dist", "power-law:1.5"])).unwrap();
+            assert_eq!(options.init_mass_dist, InitMassDist::PowerLaw { alpha: 1.5 });
+            let options = CliOptions::parse(args(&["--init-mass-dist", "fixed"])).unwrap();
+            assert_eq!(options.init_mass_dist, InitMassDist::Fixed);
+            assert!(CliOptions::parse(args(&["--init-mass-dist", "gauss"])).is_err());
+        }
+
+        #[test]
+        fn parses_event_log_path() {
+            let options = CliOptions::parse(args(&["--event-log", "events.tsv"])).unwrap();
+            assert_eq!(options.event_log.as_deref(), Some("events.tsv"));
+            assert!(CliOptions::parse(args(&["--event-log"])).is_err());
+        }
+
+        #[test]
+        fn parses_telemetry_options() {
+            let options = CliOptions::parse(args(&["--telemetry", "-"])).unwrap();
+            assert_eq!(options.telemetry.as_deref(), Some("-"));
+            assert_eq!(options.telemetry_interval_ms, config::TELEMETRY_INTERVAL_MS);
+            let options =
+                CliOptions::parse(args(&["--telemetry", "t.ndjson", "--telemetry-interval", "0"]))
+                    .unwrap();
+            assert_eq!(options.telemetry_interval_ms, 0);
+            assert!(CliOptions::parse(args(&["--telemetry-interval", "fast"])).is_err());
+        }
+
+        #[test]
+        fn parses_resume_flag() {
+            assert!(!CliOptions::parse(args(&[])).unwrap().resume);
+            assert!(CliOptions::parse(args(&["--resume"])).unwrap().resume);
+        }
+
+        #[test]
+        fn parses_schedule_path() {
+            let options = CliOptions::parse(args(&["--schedule", "words.csv"])).unwrap();
+            assert_eq!(options.schedule.as_deref(), Some("words.csv"));
+            assert!(CliOptions::parse(args(&["--schedule"])).is_err());
+        }
+
+        #[test]
+        fn rejects_missing_seed_value() {
+            assert!(CliOptions::parse(args(&["--seed"])).is_err());
+        }
+
+        #[test]
+        fn rejects_unknown_argument() {
+            assert!(CliOptions::parse(args(&["--nope"])).is_err());
+        }
+    }
+}
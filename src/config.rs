@@ -1,47 +1,371 @@
 pub const SIM_HZ: f32 = 60.0;
 pub const RENDER_HZ: f32 = 30.0;
+// 描画が間に合わないとき描画間隔を延ばす（物理の刻みは変えない）。
+// 直近の terminal.draw の所要時間（指数移動平均）が間隔の RENDER_BUSY_RATIO を超えたら延ばし、
+// その半分を下回ったら RENDER_HZ まで戻す
+pub const RENDER_ADAPTIVE: bool = true;
+pub const RENDER_HZ_MIN: f32 = 5.0;
+pub const RENDER_BUSY_RATIO: f32 = 0.5;
+pub const RENDER_DRAW_SMOOTHING: f32 = 0.2;
 pub const DT: f32 = 1.0 / SIM_HZ;
 
+// 端末セルの縦横比（高さ/幅）。描画時の Y 方向をこの値で縮める。
+pub const CELL_ASPECT: f32 = 2.0;
+
 pub const WORLD_HALF_WIDTH: f32 = 120.0;
 pub const WORLD_HALF_HEIGHT: f32 = 60.0;
 
 pub const SPATIAL_CELL_SIZE: f32 = 16.0;
 pub const SPATIAL_QUERY_RANGE_GRAVITY: i32 = 5; // 5 => 11x11
 pub const SPATIAL_QUERY_RANGE_COLLISION: i32 = 1; // 1 => 3x3
+pub const SPATIAL_CELL_IDLE_REBUILDS: u32 = 60; // 空のまま rebuild がこの回数続いたセルを回収
+pub const COLLISION_ITERATIONS: usize = 4; // 1 => 従来の1パス補正
+// 重力の近傍クエリで衝突候補の組も集め、衝突側のクエリを省く
+pub const SHARED_NEIGHBOR_QUERY: bool = true;
+// 重力計算から衝突判定までの移動（速度×dt）に加えて見込む余裕
+pub const COLLISION_CANDIDATE_MARGIN: f32 = 1.0;
+pub const SUBSTEP_MAX: usize = 4; // 1 => サブステップ無し
+pub const SUBSTEP_CANDIDATES_PER_STEP: usize = 6; // 最大衝突候補数がこれを超えるごとに1段増やす
 
 pub const INIT_WORDS: usize = 24;
+pub const SPAWN_FREE_ATTEMPTS: usize = 8;
+pub const SPAWN_FREE_MARGIN: f32 = 0.2;
+
+// UI から語を投入するときの位置の決め方
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SpawnMode {
+    // 指定位置（カメラ中心）にそのまま置く
+    #[default]
+    AtPoint,
+    // 指定位置を中心とした可視範囲内にランダムに散らす
+    RandomInView,
+    // ワールド全体にランダムに散らす
+    RandomInWorld,
+}
+
+pub const SPAWN_MODE: SpawnMode = SpawnMode::AtPoint;
+// UI から撃ち出す初速の調整幅。速さ 0 は従来どおりランダムな初速
+pub const LAUNCH_ANGLE_STEP_DEG: f32 = 15.0;
+pub const LAUNCH_SPEED_STEP: f32 = 1.0;
+pub const LAUNCH_SPEED_MAX: f32 = 40.0;
+pub const SPAWN_MASS_CAP: f32 = 50.0; // 投入時の可視質量の上限。超過分は塵になる。0 で上限なし
+pub const SPAWN_QUEUED: bool = false; // true で add_word もキュー経由で tick ごとに反映
+pub const SPAWN_PER_TICK: usize = 4; // キューから1 tick に投入する語数
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum InitMassDist {
+    Fixed,
+    PowerLaw { alpha: f32 },
+}
+
+pub const INIT_MASS_DIST: InitMassDist = InitMassDist::Fixed;
+pub const INIT_POWER_LAW_ALPHA: f32 = 2.0;
+pub const INIT_POWER_LAW_MIN_MASS: f32 = 1.0;
+pub const INIT_POWER_LAW_MAX_MASS: f32 = 120.0;
 
 pub const GRAVITY_G: f32 = 80.0;
 pub const GRAVITY_SOFTENING: f32 = 4.0;
 pub const GRAVITY_CUTOFF: f32 = 96.0;
 pub const GRAVITY_CUTOFF_FADE_START: f32 = 0.7; // cutoff比で減衰開始
+// true でカットオフ重みを減衰区間の表の線形補間で引く。解析値との差は 1e-3 未満。
+// 3000語の計測では smoothstep の直接計算より速くならなかったので既定は解析値
+pub const GRAVITY_WEIGHT_TABLE: bool = false;
+pub const GRAVITY_WEIGHT_TABLE_STEPS: usize = 256;
 pub const GRAVITY_DV_MAX: f32 = 2.5; // 1tickの速度変化量上限
 pub const GRAVITY_MIN_MASS: f32 = 0.2; // 低質量でも最低限の引力源にする
+pub const GRAVITY_FAR_FIELD_STRENGTH: f32 = 0.0; // カットオフ外の重心引力の強さ。0で完全カットオフ
+pub const GRAVITY_FAR_FIELD_CELL: f32 = 192.0; // 遠方の質量を重心にまとめる粗いセルの一辺
+pub const INERT_REJECTS_ABSORB: bool = true; // inert 語は同名の投入を吸収せず別の語として置く
+
+// 熱運動。毎 tick 可視語の速度に大きさ sqrt(温度 * dt) の等方ランダムな擾乱を足す。0で無効
+pub const THERMAL_TEMPERATURE: f32 = 0.0;
+pub const THERMAL_TEMPERATURE_STEP: f32 = 0.5;
+pub const THERMAL_TEMPERATURE_MAX: f32 = 20.0;
+pub const HIGH_PRECISION: bool = false; // true で重力の累積と積分を f64 で行う（遅い）
 
 pub const BOUNCE_DAMP: f32 = 0.9;
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MergeCriterion {
+    // 相対速度が MERGE_REL_SPEED_MAX 以下なら合体
+    RelativeSpeed,
+    // 相対運動エネルギーが重力結合エネルギーを下回れば合体
+    BindingEnergy,
+}
+
+pub const MERGE_CRITERION: MergeCriterion = MergeCriterion::BindingEnergy;
 pub const MERGE_REL_SPEED_MAX: f32 = 6.0;
+pub const MERGE_BINDING_SCALE: f32 = 0.04; // 結合エネルギー G*ma*mb/(ra+rb) に掛ける係数
+pub const MERGE_GRACE_TICKS: u32 = 20; // 合体条件を満たす接触がこの tick 数続いたら合体。0 で即時
 pub const SPLIT_REL_SPEED_MIN: f32 = 14.0;
+// true で合体上限と SPLIT_REL_SPEED_MIN の間を確率で決める。t をその間の位置として
+// 合体 (1-t)^2・分裂 t^2・反発 2t(1-t)。両端の外は従来どおり確定する
+pub const MERGE_PROBABILISTIC: bool = false;
+// 合体にも分裂にも至らない中速衝突で、相対速度1あたり両者の可視から塵へ移す質量。0 で無効
+pub const COLLISION_DAMAGE_RATE: f32 = 0.01;
 pub const TIDAL_MASS_RATIO: f32 = 6.0;
+pub const COLLISION_RESTITUTION: f32 = 0.85; // 語同士の反発係数（合体待ちの組は 0）
 pub const SPLIT_PARTS_MIN: u8 = 2;
 pub const SPLIT_PARTS_MAX: u8 = 4;
 pub const SPLIT_RADIAL_SPEED: f32 = 8.0;
+pub const MERGE_CONSERVATION_EPS: f32 = 1.0e-4; // デバッグビルドの保存則チェックの相対許容誤差
+// 走行中に差し替えられる重力・衝突のパラメータの組（'P' で PHYSICS_PRESETS を循環）。
+// カットオフを既定より広げても、近傍探索（SPATIAL_QUERY_RANGE_GRAVITY）の外の語は引かない
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PhysicsPreset {
+    pub name: &'static str,
+    pub gravity_g: f32,
+    pub gravity_cutoff: f32,
+    pub merge_rel_speed_max: f32,
+    pub merge_binding_scale: f32,
+    pub split_rel_speed_min: f32,
+    pub restitution: f32,
+}
+
+// 上の個別の定数どおりの組。起動時はこれ
+pub const DEFAULT_PHYSICS: PhysicsPreset = PhysicsPreset {
+    name: "default",
+    gravity_g: GRAVITY_G,
+    gravity_cutoff: GRAVITY_CUTOFF,
+    merge_rel_speed_max: MERGE_REL_SPEED_MAX,
+    merge_binding_scale: MERGE_BINDING_SCALE,
+    split_rel_speed_min: SPLIT_REL_SPEED_MIN,
+    restitution: COLLISION_RESTITUTION,
+};
+
+pub const PHYSICS_PRESETS: &[PhysicsPreset] = &[
+    DEFAULT_PHYSICS,
+    // 強い引力でまとまり、ぶつかっても跳ねずにくっつきやすい
+    PhysicsPreset {
+        name: "clump",
+        gravity_g: 160.0,
+        gravity_cutoff: 96.0,
+        merge_rel_speed_max: 12.0,
+        merge_binding_scale: 0.15,
+        split_rel_speed_min: 28.0,
+        restitution: 0.3,
+    },
+    // 引力が弱く届く範囲も狭い。よく跳ね、合体しにくく砕けやすい
+    PhysicsPreset {
+        name: "scatter",
+        gravity_g: 30.0,
+        gravity_cutoff: 48.0,
+        merge_rel_speed_max: 2.5,
+        merge_binding_scale: 0.01,
+        split_rel_speed_min: 9.0,
+        restitution: 0.98,
+    },
+    // 引力をほぼ切り、衝突だけで動く気体に近い状態
+    PhysicsPreset {
+        name: "gas",
+        gravity_g: 5.0,
+        gravity_cutoff: 32.0,
+        merge_rel_speed_max: 1.0,
+        merge_binding_scale: 0.005,
+        split_rel_speed_min: 20.0,
+        restitution: 1.0,
+    },
+];
+pub const MASS_DRIFT_WARN_RATIO: f32 = 1.0e-3; // 総質量と投入量の差がこの比を超えたらヘッダで警告
+
+// 合体・分裂時に語のメタデータをどう引き継ぐか
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MetaPolicy {
+    // 親のメタは消滅し、新しい語には何も残らない
+    Drop,
+    // 合体では質量の大きい側のメタだけを残し、分裂では各破片に複製する
+    Inherit,
+    // 合体では両者を統合（オブジェクト同士はキーの和、それ以外は配列）し、分裂では複製する
+    Combine,
+}
+
+pub const META_POLICY: MetaPolicy = MetaPolicy::Combine;
 
 pub const WEATHERING_RATE: f32 = 0.02;
+pub const WORD_MAX_AGE: f32 = 0.0; // 秒。0 で寿命による塵化を無効化
+pub const WORD_OLD_AGE_WEATHERING_GAIN: f32 = 20.0; // 寿命を1周期超えるごとの風化率の上乗せ倍率
+pub const WORD_EXPIRE_VISIBLE_MASS: f32 = 0.01; // 寿命超過語はこれを下回ると除去
 pub const AUTOGENESIS_RATE: f32 = 0.08;
+// 新規復活語を、セルごとの可視質量（密度場）に比例した確率で選んだセルの中へ置く確率
+pub const AUTOGENESIS_VALLEY_PROB: f32 = 0.7;
+pub const AUTOGENESIS_VALLEY_MIN_MASS: f32 = 12.0; // 密度場の合計がこれ未満なら一様に置く
 
 pub const MIN_VISIBLE_MASS: f32 = 0.2;
 
+// 可視語がこれを下回ると autogenesis を始め、K_VISIBLE_MIN + AUTOGENESIS_HYSTERESIS に
+// 達するまで続ける。境界付近で毎 tick 発火・停止を繰り返さないための幅
 pub const K_VISIBLE_MIN: usize = 40;
+pub const AUTOGENESIS_HYSTERESIS: usize = 8;
 pub const K_VISIBLE_MAX: usize = 400;
 
 pub const WORD_RADIUS_BASE: f32 = 1.2;
 pub const WORD_RADIUS_SCALE: f32 = 0.06;
+pub const RADIUS_FROM_VISIBLE: bool = true; // false で塵も含む総質量から半径を決める
+// true で半径を BASE + LOG_SCALE * ln(1 + mass) にし、巨大語が画面を埋めないようにする
+pub const RADIUS_LOG: bool = false;
+pub const WORD_RADIUS_LOG_SCALE: f32 = 0.25;
+// true で合体・吸収後の半径を面積の和 sqrt(r_a^2 + r_b^2) から決める（質量は従来どおり保存）。
+// 風化などで質量が変わったときは面積を質量の比で増減させる
+pub const RADIUS_VOLUME_CONSERVING: bool = false;
+// true で tick の終わりに全語を総質量の重心が原点に来るよう平行移動する（速度は変えない）。
+// カメラを原点に置いたまま系全体の漂流を打ち消して観察できる
+pub const AUTO_RECENTER: bool = false;
 
 pub const SUN_PULSE_RADIUS: f32 = 32.0;
 pub const SUN_PULSE_STRENGTH: f32 = 14.0;
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SunPulseMode {
+    // 半径内の語を常に一定の強さで外向きに押す
+    Constant,
+    // 強さを sin で時間変調し、押しと引きを周期的に繰り返す
+    Wave,
+}
+
+pub const SUN_PULSE_MODE: SunPulseMode = SunPulseMode::Wave;
+pub const SUN_PULSE_PERIOD: f32 = 2.0; // 秒
+
+// 'j' のヒートマップ。合体・分裂の位置を最大 HEATMAP_CAPACITY 件まで覚え、
+// HEATMAP_DECAY_SECS で 1/e に減衰させ、HEATMAP_WINDOW_SECS より古いものは捨てる
+pub const HEATMAP_CAPACITY: usize = 4096;
+pub const HEATMAP_DECAY_SECS: f32 = 8.0;
+pub const HEATMAP_WINDOW_SECS: f32 = 40.0;
+
+// マウスの左ドラッグで語を掴む。押したセルからこのセル数以内（表面まで）の語を拾う
+pub const DRAG_PICK_CELLS: f32 = 3.0;
+// 離したときの速度 = 直近のドラッグ速度（指数移動平均）* SCALE。上限 MAX
+pub const DRAG_VEL_SMOOTHING: f32 = 0.3;
+pub const DRAG_THROW_SCALE: f32 = 1.0;
+pub const DRAG_THROW_MAX_SPEED: f32 = 80.0;
+
+// "zone" で置く摩擦ゾーン。内側の語の速度を毎秒 exp(-damp) 倍に落とす
+pub const FRICTION_ZONE_RADIUS: f32 = 40.0;
+pub const FRICTION_ZONE_DAMP: f32 = 1.5;
+pub const FRICTION_ZONE_MAX: usize = 8; // 超えたら古いものから外す
+
+// 生成・合体・分裂・太陽設置で端末ベルを鳴らす。通知はこの間隔内でまとめて1回にする
+pub const SOUND_NOTIFY: bool = false;
+pub const SOUND_THROTTLE_MS: u64 = 500;
+
+// 'y' で表示するフォーカス語の予測軌道（PREDICT_DT 刻みで PREDICT_STEPS 点）
+pub const PREDICT_STEPS: usize = 90;
+pub const PREDICT_DT: f32 = 1.0 / 15.0;
+pub const PREDICT_ATTRACTORS: usize = 4; // 予測に使う近傍の引力源の数
+
+// --telemetry の統計出力間隔。0 で無効
+pub const TELEMETRY_INTERVAL_MS: u64 = 1000;
+
+pub const TRAIL_MIN_DISTANCE: f32 = 0.5; // 直近の記録点からこれ以上動いたときだけ軌跡に残す
+
+pub const CLOSEST_PAIRS_TOP_K: usize = 3;
+pub const HIGHLIGHT_BLINK_MS: u128 = 250;
+
+pub const GRID_SHOW_COUNTS: bool = true; // '#' のグリッド表示で各セルの語数も出す
+
+// 語数が多いときの描画の簡略化（LOD）。語数をズームで割った値で段階を決める
+pub const LOD_ENABLED: bool = true;
+pub const LOD_REDUCED_WORDS: f32 = 120.0; // 超えたら画面中央から遠い小質量語を頭文字だけにする
+pub const LOD_MINIMAL_WORDS: f32 = 300.0; // 超えたら小質量語はすべて点にし、軌跡も描かない
+pub const LOD_FULL_MASS: f32 = 20.0; // 可視質量がこれ以上の語は常にフル描画
+pub const LOD_NEAR_CELLS: f32 = 20.0; // 画面中央からこのセル数以内は「遠方」扱いしない
+pub const LOD_POINT_GLYPH: char = '•';
+
+pub const DENSITY_SHADING: bool = true;
+pub const DENSITY_SHADE_STEP: f32 = 12.0;
+
+// 生まれたての語の描画優先度に足す加点（質量と同じ単位）。RECENCY_PRIORITY_AGE 秒で 0 まで減る。
+// 0 なら質量だけで前後を決める
+pub const RECENCY_PRIORITY_WEIGHT: f32 = 30.0;
+pub const RECENCY_PRIORITY_AGE: f32 = 3.0;
+pub const FOCUS_ABOVE_EFFECTS: bool = true;
+
+// エフェクトと語のどちらを前面に描くか
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LayerOrder {
+    // 軌跡 → 語 → エフェクトの順で、エフェクトが常に最前面
+    #[default]
+    EffectsOnTop,
+    // エフェクトは語と軌跡の背後で、空いたセルにだけ出る
+    WordsOnTop,
+}
+
+pub const LAYER_ORDER: LayerOrder = LayerOrder::EffectsOnTop;
+pub const FOCUS_FRAME: bool = true; // フォーカス語を罫線の枠で囲む
+pub const DRAW_DECONFLICT_SHIFTS: usize = 3; // 重なった語を下へずらす最大行数。0 で無効
+// 画面外の重い語の方向を画面端の矢印で示す。出す数の上限（0 で無効）と可視質量のしきい値。
+// フォーカス語はしきい値によらず出す
+pub const OFFSCREEN_INDICATOR_MAX: usize = 6;
+pub const OFFSCREEN_INDICATOR_MIN_MASS: f32 = 20.0;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum WordColorMode {
+    // 質量・速度のしきい値で段階的に切り替える従来の配色
+    #[default]
+    Discrete,
+    // 質量・速度を HSV 上で連続的に補間する
+    Continuous,
+}
+
+pub const WORD_COLOR_MODE: WordColorMode = WordColorMode::Discrete;
+pub const COLOR_MASS_REF: f32 = 30.0; // この質量で色相が重い側の端に達する
+pub const COLOR_SPEED_REF: f32 = 14.0; // この速さで色相が高速側（シアン）に寄り切る
+
+pub const WORD_WRAP_WIDTH: usize = 12; // 折り返し時の1行あたりの最大セル数
+// 1語の描画セル数の上限（全角は2、省略記号を含む）。超える語は省略記号で打ち切る。
+// TEXT_MAX_DRAW 以上や 0 なら実質無制限。フォーカス語には掛けない
+pub const MAX_DRAW_CELLS_PER_WORD: usize = 60;
+pub const VERTICAL_TEXT: bool = false; // 起動時に縦書きで描く（'v' で切り替え）
+
+// 'O' で保存する全域ビューの大きさ（セル）と保存先
+pub const OVERVIEW_WIDTH: u16 = 240;
+pub const OVERVIEW_HEIGHT: u16 = 60;
+pub const OVERVIEW_PATH: &str = "wordcosmo2-overview.txt";
+
+// この tick 数ごとに AUTOSAVE_PATH へ全体を書き出す。0 で無効。--resume で起動時に読み戻す
+pub const AUTOSAVE_PATH: &str = "autosave.json";
+pub const AUTOSAVE_INTERVAL_TICKS: u64 = 1800;
+
+// 'c' は状態文字列をクリップボードへ写し、クリップボードが使えなければこのファイルに書く。
+// 'C' はクリップボードの状態文字列を、無ければこのファイルを読んで復元する
+pub const SHARE_PATH: &str = "share.txt";
+
+// 'u' で巻き戻せるよう、この tick 間隔で状態を写して古い順に最大 MAX 件まで残す。
+// 写した時点の続きは、巻き戻してから進めても同じ展開になる
+pub const TIME_TRAVEL_INTERVAL_TICKS: u64 = 120;
+pub const TIME_TRAVEL_CHECKPOINTS_MAX: usize = 30;
+
+// 'E' で書き出す語リスト CSV。塵のみの語を含めるかどうか
+pub const CSV_EXPORT_PATH: &str = "words.csv";
+pub const CSV_INCLUDE_DUST: bool = false;
+
 pub const EFFECT_CAPACITY: usize = 512;
 pub const EFFECT_TTL: f32 = 0.6;
+pub const EFFECT_CAPACITY_STEP: usize = 64;
+pub const EFFECT_CAPACITY_MAX: usize = 4096;
+pub const EFFECT_TTL_STEP: f32 = 0.1;
+pub const EFFECT_TTL_MAX: f32 = 3.0;
+pub const EFFECT_MASS_REF: f32 = 20.0; // この質量の合体で基準の強さ（1.0）
+pub const EFFECT_INTENSITY_MIN: f32 = 0.5;
+pub const EFFECT_INTENSITY_MAX: f32 = 4.0;
+pub const EFFECT_RING_MAX: usize = 48; // 1リングあたりの粒子数上限
+
+// 合体の表示補間。物理は即座に合体させ、元の語の幻影を MERGE_ANIM_SECS かけて合体語へ寄せる。
+// 0 で無効。UI が取り出さない間に溜める記録の上限が MERGE_ANIM_RECORDS_MAX
+pub const MERGE_ANIM_SECS: f32 = 0.35;
+pub const MERGE_ANIM_RECORDS_MAX: usize = 64;
+pub const MERGE_ANIM_STREAK_GLYPH: char = '·';
+// 合体・分裂リングの色を段階的に変える強さのしきい値
+pub const EFFECT_COLOR_STEP_LOW: f32 = 1.0;
+pub const EFFECT_COLOR_STEP_HIGH: f32 = 2.0;
+// 相対速度がこれ以上の衝突で衝突点から衝撃波リングを出す
+pub const SHOCKWAVE_REL_SPEED_MIN: f32 = 10.0;
+pub const SHOCKWAVE_SPEED_SCALE: f32 = 3.0; // 相対速度1あたりのリングの広がる速さ
+pub const SHOCKWAVE_RADIUS_SCALE: f32 = 1.0; // 相対速度1あたりの最大半径
+pub const SHOCKWAVE_RADIUS_MAX: f32 = 40.0;
+pub const SHOCKWAVE_GLYPH: char = 'o';
+
+// 'b' で張るばねの強さ（力 = SPRING_K * 伸び）。自然長は張った時点の距離
+pub const SPRING_K: f32 = 4.0;
+pub const SPRING_GLYPH: char = '-';
 
 pub const WORD_JOIN_SEP: char = '\u{1F}';
@@ -0,0 +1,8 @@
+pub mod cli;
+pub mod config;
+pub mod core;
+pub mod render;
+pub mod share;
+pub mod spatial;
+pub mod types;
+pub mod ui;
@@ -1,10 +1,6 @@
-mod config;
-mod core;
-mod render;
-mod spatial;
-mod types;
-mod ui;
+use wordcosmo2::{cli, ui};
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
-    ui::run()
+    let options = cli::CliOptions::parse(std::env::args().skip(1))?;
+    ui::run(options)
 }
@@ -1,11 +1,25 @@
-use crate::types::{
-    ColorId, EffectParticle, Vec2, WordId, WordSnapshot, TEXT_MAX_DRAW, TRAIL_LEN,
+use std::{
+    borrow::Cow,
+    collections::{HashMap, HashSet},
 };
 
+use crate::{
+    config::{self, LayerOrder, WordColorMode},
+    types::{
+        char_cell_width, ActivityMap, ColorId, EffectKind, EffectParticle, MergeRecord, Vec2,
+        WordId, WordSnapshot, TEXT_MAX_DRAW, TRAIL_LEN,
+    },
+};
+
+// 全角文字の右半分のセル。表示側ではこのセルを出力しない。
+pub const WIDE_CONTINUATION: char = '\0';
+pub const ELLIPSIS: char = '…';
+
 #[derive(Clone, Copy, Debug)]
 pub struct Camera {
     pub pos: Vec2,
     pub zoom: f32,
+    pub aspect: f32,
 }
 
 impl Default for Camera {
@@ -13,28 +27,228 @@ impl Default for Camera {
         Self {
             pos: Vec2::ZERO,
             zoom: 1.0,
+            aspect: config::CELL_ASPECT,
         }
     }
 }
 
+impl Camera {
+    // ワールド座標を画面のセル座標（小数）へ。縦はセルの縦横比のぶん縮める
+    pub fn world_to_screen_f32(&self, pos: Vec2, viewport: Viewport) -> (f32, f32) {
+        let half_w = viewport.width as f32 / 2.0;
+        let half_h = viewport.height as f32 / 2.0;
+        (
+            (pos.x - self.pos.x) * self.zoom + half_w,
+            (pos.y - self.pos.y) * self.zoom / self.aspect + half_h,
+        )
+    }
+
+    // pos が乗るセル。画面外なら負や viewport 以上の値になる
+    pub fn world_to_screen(&self, pos: Vec2, viewport: Viewport) -> (i32, i32) {
+        let (x, y) = self.world_to_screen_f32(pos, viewport);
+        (x.round() as i32, y.round() as i32)
+    }
+
+    pub fn screen_to_world(&self, x: f32, y: f32, viewport: Viewport) -> Vec2 {
+        let half_w = viewport.width as f32 / 2.0;
+        let half_h = viewport.height as f32 / 2.0;
+        Vec2::new(
+            (x - half_w) / self.zoom + self.pos.x,
+            (y - half_h) * self.aspect / self.zoom + self.pos.y,
+        )
+    }
+}
+
 #[derive(Clone, Copy, Debug)]
 pub struct Viewport {
     pub width: u16,
     pub height: u16,
 }
 
+pub const DENSITY_GLYPHS: [char; 4] = ['░', '▒', '▓', '█'];
+
+// 描画優先度。通常の語は可視質量をそのまま優先度として使う。
+// 軌跡は可視質量の 0.3 倍以下、エフェクトは LayerOrder に応じて EFFECT_PRIORITY（最前面）か
+// EFFECT_PRIORITY_UNDER_WORDS（何も描かれていないセルだけ）。
+pub const EFFECT_PRIORITY: f32 = 1.0e9;
+pub const EFFECT_PRIORITY_UNDER_WORDS: f32 = 0.0;
+pub const FOCUS_PRIORITY: f32 = f32::INFINITY;
+// 予測軌道は空きセルにだけ出る程度の優先度
+pub const PREDICTION_PRIORITY: f32 = 0.0;
+pub const PREDICTION_GLYPH: char = '∘';
+pub const OVERLAY_GLYPH: char = '.';
+// フォーカス語の枠。語本体より低く、空きセルと軌跡の上にだけ出て他の語の文字は消さない
+pub const FOCUS_FRAME_PRIORITY: f32 = 0.1;
+// 左上・右上・左下・右下・横・縦
+pub const FOCUS_FRAME_GLYPHS: [char; 6] = ['┌', '┐', '└', '┘', '─', '│'];
+// グリッドは最背面。何も描かれないセルにだけ出る
+pub const GRID_PRIORITY: f32 = f32::MIN;
+// ヒートマップはグリッドより上、他のすべてより下
+pub const HEATMAP_PRIORITY: f32 = f32::MIN / 2.0;
+// 最大セルに対する割合がこれ未満のセルは塗らない
+pub const HEATMAP_MIN_RATIO: f32 = 0.05;
+// 縦線・横線・交点
+pub const GRID_GLYPHS: [char; 3] = ['┊', '┈', '┼'];
+// エフェクトより下に置く場合のフォーカス語の優先度（他の語よりは常に上）
+pub const FOCUS_PRIORITY_BELOW_EFFECTS: f32 = 1.0e8;
+// 画面外の語を指す矢印。エフェクトより上に出す
+pub const OFFSCREEN_PRIORITY: f32 = 2.0e9;
+// 右から反時計回りに 45° ずつ
+pub const OFFSCREEN_ARROWS: [char; 8] = ['→', '↗', '↑', '↖', '←', '↙', '↓', '↘'];
+
+// 語ごとの色を利用者が決める関数。None を返した語は color_mode の既定色になる
+pub type WordColorFn<'a> = &'a dyn Fn(&WordSnapshot) -> Option<ColorId>;
+
+#[derive(Clone, Copy, Default)]
+pub struct DrawOptions<'a> {
+    pub highlight_ids: &'a [WordId],
+    pub density_shading: bool,
+    pub focus_above_effects: bool,
+    pub word_wrap: bool,
+    pub color_mode: WordColorMode,
+    // 同じ開始セルに重なった語を下へずらす最大行数。0 でずらさない
+    pub deconflict_shifts: usize,
+    // フォーカス語の予測軌道（ワールド座標）
+    pub prediction: &'a [Vec2],
+    // フォーカス語を中心に重力の減衰開始半径とカットオフ半径の円を描く
+    pub gravity_overlay: bool,
+    pub focus_frame: bool,
+    pub layer_order: LayerOrder,
+    // この刻み（ワールド座標）でセル境界を描く。0 で描かない
+    pub grid_cell_size: f32,
+    // グリッドの各セルに含まれる語数を左上に出す
+    pub grid_counts: bool,
+    // 語数とズームに応じて小質量語と軌跡を簡略化する
+    pub lod: bool,
+    // 語の各文字を開始セルから下へ1行ずつ積む。word_wrap より優先する
+    pub vertical_text: bool,
+    // ばねで結ばれた語の組の位置（ワールド座標）。語より下に線で描く
+    pub springs: &'a [(Vec2, Vec2)],
+    // 新しい語ほど前面に出す加点の重み。0 で質量だけの優先度
+    pub recency_weight: f32,
+    // 摩擦ゾーン (中心, 半径)。境界を薄く描く
+    pub friction_zones: &'a [(Vec2, f32)],
+    // 合体・分裂の活動量。最も活発なセルを基準に濃淡をつけ、多い方の種類で色を分ける
+    pub heatmap: Option<&'a ActivityMap>,
+    // 合体アニメーション中の元の語の幻影（merge_ghosts で作る）と、幻影から合体語への軌跡。
+    // merge_hidden の語はアニメーションが終わるまで描かない（フォーカス語は除く）
+    pub merge_ghosts: &'a [WordSnapshot],
+    pub merge_streaks: &'a [(Vec2, Vec2)],
+    pub merge_hidden: &'a [WordId],
+    // 既定の語色の差し替え。フォーカス語（赤）・ハイライト・合体間近の警告色の方が優先する
+    pub color_fn: Option<WordColorFn<'a>>,
+    // 1語の描画セル数の上限（全角は2、省略記号を含む）。0 なら TEXT_MAX_DRAW まで。
+    // フォーカス語には掛けない
+    pub max_cells_per_word: usize,
+    // 画面外の語の方向を端に示す矢印の数の上限（0 で出さない）と、対象にする可視質量の下限。
+    // フォーカス語は下限によらず先に出す
+    pub offscreen_indicators: usize,
+    pub offscreen_min_mass: f32,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LodLevel {
+    #[default]
+    Full,
+    // 画面中央から遠い小質量語は頭文字だけ、軌跡は1点おき
+    Reduced,
+    // 小質量語は点だけで軌跡なし
+    Minimal,
+}
+
+impl LodLevel {
+    pub fn label(self) -> &'static str {
+        match self {
+            LodLevel::Full => "full",
+            LodLevel::Reduced => "reduced",
+            LodLevel::Minimal => "minimal",
+        }
+    }
+}
+
+// 縮小するほど同じ語数でも画面が混むので、語数をズームで割った値で段階を決める
+pub fn lod_level(word_count: usize, zoom: f32) -> LodLevel {
+    let load = word_count as f32 / zoom.max(f32::EPSILON);
+    if load > config::LOD_MINIMAL_WORDS {
+        LodLevel::Minimal
+    } else if load > config::LOD_REDUCED_WORDS {
+        LodLevel::Reduced
+    } else {
+        LodLevel::Full
+    }
+}
+
+// 質量に、年齢 0 で recency_weight、RECENCY_PRIORITY_AGE で 0 になる加点を足す
+fn word_priority(word: &WordSnapshot, recency_weight: f32) -> f32 {
+    if recency_weight <= 0.0 || config::RECENCY_PRIORITY_AGE <= 0.0 {
+        return word.mass_visible;
+    }
+    let freshness = (1.0 - word.age / config::RECENCY_PRIORITY_AGE).clamp(0.0, 1.0);
+    word.mass_visible + recency_weight * freshness
+}
+
+// 語ごとの描き方。フォーカス語・強調語・大質量語は常に Full
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum WordDetail {
+    Full,
+    Initial,
+    Point,
+}
+
+fn word_detail(
+    lod: LodLevel,
+    word: &WordSnapshot,
+    (sx, sy): (i32, i32),
+    viewport: Viewport,
+    keep: bool,
+) -> WordDetail {
+    if keep || word.mass_visible >= config::LOD_FULL_MASS {
+        return WordDetail::Full;
+    }
+    match lod {
+        LodLevel::Full => WordDetail::Full,
+        LodLevel::Minimal => WordDetail::Point,
+        LodLevel::Reduced => {
+            let dx = sx as f32 - viewport.width as f32 / 2.0;
+            let dy = (sy as f32 - viewport.height as f32 / 2.0) * config::CELL_ASPECT;
+            if dx * dx + dy * dy > config::LOD_NEAR_CELLS * config::LOD_NEAR_CELLS {
+                WordDetail::Initial
+            } else {
+                WordDetail::Full
+            }
+        }
+    }
+}
+
+// 軌跡を何点おきに描くか。0 で描かない
+fn trail_stride(lod: LodLevel, word: &WordSnapshot, keep: bool) -> usize {
+    if keep || word.mass_visible >= config::LOD_FULL_MASS {
+        return 1;
+    }
+    match lod {
+        LodLevel::Full => 1,
+        LodLevel::Reduced => 2,
+        LodLevel::Minimal => 0,
+    }
+}
+
 #[derive(Clone, Copy, Debug)]
 pub struct RenderCell {
     pub ch: char,
-    pub mass: f32,
+    pub priority: f32,
     pub color: ColorId,
+    // ch に重ねる幅 0 の文字（結合文字など）。NO_MARK でなし。1セル1文字まで
+    pub mark: char,
 }
 
+pub const NO_MARK: char = '\0';
+
 #[derive(Debug)]
 pub struct FrameBuffer {
     width: u16,
     height: u16,
     cells: Vec<RenderCell>,
+    density: Vec<f32>,
 }
 
 impl FrameBuffer {
@@ -43,6 +257,7 @@ impl FrameBuffer {
             width,
             height,
             cells: Vec::new(),
+            density: Vec::new(),
         };
         buffer.resize(width, height);
         buffer
@@ -57,19 +272,48 @@ impl FrameBuffer {
                 len,
                 RenderCell {
                     ch: ' ',
-                    mass: f32::NEG_INFINITY,
+                    priority: f32::NEG_INFINITY,
                     color: ColorId::White,
+                    mark: NO_MARK,
                 },
             );
         }
+        self.density.resize(len, 0.0);
         self.clear();
     }
 
     pub fn clear(&mut self) {
         for cell in &mut self.cells {
             cell.ch = ' ';
-            cell.mass = f32::NEG_INFINITY;
+            cell.priority = f32::NEG_INFINITY;
             cell.color = ColorId::White;
+            cell.mark = NO_MARK;
+        }
+        self.density.fill(0.0);
+    }
+
+    pub fn density(&self, x: u16, y: u16) -> f32 {
+        debug_assert!(x < self.width && y < self.height, "density() out of bounds");
+        self.density[(y as usize) * (self.width as usize) + (x as usize)]
+    }
+
+    fn add_density(&mut self, x: i32, y: i32, mass: f32) {
+        if x < 0 || y < 0 || x >= self.width as i32 || y >= self.height as i32 {
+            return;
+        }
+        let idx = (y as usize) * (self.width as usize) + (x as usize);
+        self.density[idx] += mass;
+    }
+
+    // 何も描かれていないセルにだけ累積質量の濃淡グリフを敷く
+    fn apply_density_shading(&mut self, step: f32) {
+        for (cell, &density) in self.cells.iter_mut().zip(self.density.iter()) {
+            if cell.priority != f32::NEG_INFINITY || density < step {
+                continue;
+            }
+            let level = ((density / step) as usize).min(DENSITY_GLYPHS.len()) - 1;
+            cell.ch = DENSITY_GLYPHS[level];
+            cell.color = ColorId::Shade;
         }
     }
 
@@ -87,26 +331,68 @@ impl FrameBuffer {
         self.cells[idx]
     }
 
-    fn set(&mut self, x: u16, y: u16, ch: char, mass: f32, color: ColorId) {
+    // WIDE_CONTINUATION のセルは出力せず、行ごとに末尾の空白を落とす
+    pub fn to_text(&self) -> String {
+        let mut out = String::new();
+        for y in 0..self.height {
+            let row = (0..self.width)
+                .map(|x| self.get(x, y))
+                .filter(|cell| cell.ch != WIDE_CONTINUATION)
+                .flat_map(|cell| Some(cell.ch).into_iter().chain(cell.visible_mark()))
+                .collect::<String>();
+            out.push_str(row.trim_end());
+            out.push('\n');
+        }
+        out
+    }
+
+    fn is_free(&self, x: i32, y: i32) -> bool {
+        if x < 0 || y < 0 || x >= self.width as i32 || y >= self.height as i32 {
+            return false;
+        }
+        self.get(x as u16, y as u16).priority == f32::NEG_INFINITY
+    }
+
+    fn set(&mut self, x: u16, y: u16, ch: char, priority: f32, color: ColorId) {
         if x >= self.width || y >= self.height {
             return;
         }
         let idx = (y as usize) * (self.width as usize) + (x as usize);
         let cell = &mut self.cells[idx];
-        if mass >= cell.mass {
-            cell.mass = mass;
+        if priority >= cell.priority {
+            cell.priority = priority;
             cell.ch = ch;
             cell.color = color;
+            cell.mark = NO_MARK;
+        }
+    }
+
+    // 同じ優先度で描いた文字がまだ残っているときだけ重ねる（上書きされた文字には付けない）
+    fn set_mark(&mut self, x: u16, y: u16, mark: char, priority: f32) {
+        if x >= self.width || y >= self.height {
+            return;
+        }
+        let idx = (y as usize) * (self.width as usize) + (x as usize);
+        let cell = &mut self.cells[idx];
+        if cell.priority == priority && cell.ch != WIDE_CONTINUATION {
+            cell.mark = mark;
         }
     }
 }
 
+impl RenderCell {
+    pub fn visible_mark(&self) -> Option<char> {
+        (self.mark != NO_MARK).then_some(self.mark)
+    }
+}
+
 pub fn draw(
     snapshot: &[WordSnapshot],
     effects: &[EffectParticle],
     focus_word_id: Option<WordId>,
     camera: &Camera,
     viewport: Viewport,
+    options: &DrawOptions,
     frame: &mut FrameBuffer,
 ) {
     if frame.width() != viewport.width || frame.height() != viewport.height {
@@ -115,168 +401,1025 @@ pub fn draw(
         frame.clear();
     }
 
-    let half_w = viewport.width as f32 / 2.0;
-    let half_h = viewport.height as f32 / 2.0;
-
+    if let Some(map) = options.heatmap {
+        draw_heatmap(map, camera, viewport, frame);
+    }
+    if options.grid_cell_size > 0.0 {
+        let cell_size = options.grid_cell_size;
+        draw_grid(snapshot, cell_size, options.grid_counts, camera, viewport, frame);
+    }
+    let lod = if options.lod {
+        lod_level(snapshot.len(), camera.zoom)
+    } else {
+        LodLevel::Full
+    };
+    let keep = |word: &WordSnapshot| {
+        focus_word_id == Some(word.id) || options.highlight_ids.contains(&word.id)
+    };
     for word in snapshot {
-        draw_trail(word, camera, viewport, frame, half_w, half_h);
+        let stride = trail_stride(lod, word, keep(word));
+        draw_trail(word, stride, camera, viewport, frame);
+    }
+    draw_prediction(options.prediction, camera, viewport, frame);
+    for &(a, b) in options.springs {
+        draw_segment((a, b), config::SPRING_GLYPH, ColorId::Gray, camera, viewport, frame);
+    }
+    for &(a, b) in options.merge_streaks {
+        let glyph = config::MERGE_ANIM_STREAK_GLYPH;
+        draw_segment((a, b), glyph, ColorId::Trail, camera, viewport, frame);
+    }
+    for &(center, radius) in options.friction_zones {
+        draw_circle(center, radius, ColorId::Gray, camera, viewport, frame);
+    }
+    if options.gravity_overlay
+        && let Some(word) = snapshot.iter().find(|w| focus_word_id == Some(w.id))
+    {
+        let cutoff = config::GRAVITY_CUTOFF;
+        let fade_start = cutoff * config::GRAVITY_CUTOFF_FADE_START;
+        draw_circle(word.pos, fade_start, ColorId::Blue, camera, viewport, frame);
+        draw_circle(word.pos, cutoff, ColorId::Gray, camera, viewport, frame);
     }
 
-    for word in snapshot {
-        let sx = ((word.pos.x - camera.pos.x) * camera.zoom + half_w).round() as i32;
-        let sy = ((word.pos.y - camera.pos.y) * camera.zoom + half_h).round() as i32;
-        if sy < 0 || sy >= viewport.height as i32 {
-            continue;
+    if options.density_shading {
+        for word in snapshot {
+            accumulate_density(word, camera, viewport, frame);
         }
+    }
+
+    let shifts = deconflict_shifts(snapshot, focus_word_id, camera, viewport, options);
+    let anchor = |i: usize| {
+        let (x, y) = word_anchor(&snapshot[i], camera, viewport);
+        (x, y + shifts.get(i).copied().unwrap_or(0))
+    };
 
-        let color = if focus_word_id == Some(word.id) {
-            ColorId::Red
+    let mut focused = None;
+    for (i, word) in snapshot.iter().enumerate() {
+        if focus_word_id == Some(word.id) {
+            focused = Some(word);
+            continue;
+        }
+        if options.merge_hidden.contains(&word.id) {
+            continue;
+        }
+        let word = &*capped_word(word, options.max_cells_per_word);
+        let color = if options.highlight_ids.contains(&word.id) {
+            ColorId::Highlight
+        } else if word.merge_pending {
+            ColorId::MergeWarning
         } else {
-            word_color(word)
+            base_color(word, options)
         };
-        let mut text_len = word.text_len.min(TEXT_MAX_DRAW);
-        if word.text_len > TEXT_MAX_DRAW && text_len > 0 && word.text[text_len - 1] == '-' {
-            text_len -= 1;
-        }
-        for i in 0..text_len {
-            let x = sx + i as i32;
-            if x < 0 || x >= viewport.width as i32 {
-                continue;
+        let anchor = anchor(i);
+        let priority = word_priority(word, options.recency_weight);
+        match word_detail(lod, word, anchor, viewport, keep(word)) {
+            WordDetail::Full => draw_word(word, anchor, priority, color, viewport, options, frame),
+            WordDetail::Initial => {
+                if let Some(&ch) = word.text[..word.text_len].first() {
+                    draw_glyph(ch, anchor, priority, color, viewport, frame);
+                }
+            }
+            WordDetail::Point => {
+                let ch = config::LOD_POINT_GLYPH;
+                draw_glyph(ch, anchor, priority, color, viewport, frame);
             }
-            let ux = x as u16;
-            let uy = sy as u16;
-            let ch = word.text[i];
-            frame.set(ux, uy, ch, word.mass_visible, color);
         }
     }
 
+    // 合体アニメーションの幻影。物理上はもう無い語なので位置のずらしも LOD もかけない
+    for ghost in options.merge_ghosts {
+        let ghost = &*capped_word(ghost, options.max_cells_per_word);
+        let color = base_color(ghost, options);
+        let anchor = word_anchor(ghost, camera, viewport);
+        let priority = word_priority(ghost, options.recency_weight);
+        draw_word(ghost, anchor, priority, color, viewport, options, frame);
+    }
+
+    if let Some(word) = focused
+        && options.focus_frame
+    {
+        let anchor = word_anchor(word, camera, viewport);
+        draw_focus_frame(word, anchor, viewport, options, frame);
+    }
+
+    // フォーカス語は他の語より後に最大優先度で描く
+    if let Some(word) = focused
+        && !options.focus_above_effects
+    {
+        let priority = FOCUS_PRIORITY_BELOW_EFFECTS;
+        let anchor = word_anchor(word, camera, viewport);
+        draw_word(word, anchor, priority, ColorId::Red, viewport, options, frame);
+    }
+
+    let effect_priority = match options.layer_order {
+        LayerOrder::EffectsOnTop => EFFECT_PRIORITY,
+        LayerOrder::WordsOnTop => EFFECT_PRIORITY_UNDER_WORDS,
+    };
     for effect in effects {
-        let sx = ((effect.pos.x - camera.pos.x) * camera.zoom + half_w).round() as i32;
-        let sy = ((effect.pos.y - camera.pos.y) * camera.zoom + half_h).round() as i32;
+        if let EffectKind::Shockwave { radius, .. } = effect.kind {
+            let (glyph, color) = (effect.glyph, effect.color);
+            let ring = RingStyle { glyph, priority: effect_priority, color };
+            draw_ring(effect.pos, radius, ring, camera, viewport, frame);
+            continue;
+        }
+        let (sx, sy) = camera.world_to_screen(effect.pos, viewport);
         if sx >= 0 && sy >= 0 {
             let ux = sx as u16;
             let uy = sy as u16;
             if ux < viewport.width && uy < viewport.height {
-                frame.set(ux, uy, effect.glyph, 1.0e9, effect.color);
+                frame.set(ux, uy, effect.glyph, effect_priority, effect.color);
             }
         }
     }
+
+    if let Some(word) = focused
+        && options.focus_above_effects
+    {
+        let anchor = word_anchor(word, camera, viewport);
+        draw_word(word, anchor, FOCUS_PRIORITY, ColorId::Red, viewport, options, frame);
+    }
+
+    draw_offscreen_indicators(snapshot, focus_word_id, camera, viewport, options, frame);
+
+    if options.density_shading {
+        frame.apply_density_shading(config::DENSITY_SHADE_STEP);
+    }
 }
 
-fn draw_trail(
-    word: &WordSnapshot,
+// 開始セルが画面外にある語のうち、フォーカス語と可視質量が offscreen_min_mass 以上の語を
+// 質量 / 距離² の大きい順（近くて重いほど先）に上限まで選ぶ。画面中心から語へ向かう線が
+// 画面端と交わるセルに、その向きの矢印を置く
+fn draw_offscreen_indicators(
+    snapshot: &[WordSnapshot],
+    focus_word_id: Option<WordId>,
     camera: &Camera,
     viewport: Viewport,
+    options: &DrawOptions,
     frame: &mut FrameBuffer,
-    half_w: f32,
-    half_h: f32,
 ) {
-    if word.trail_len == 0 {
+    let max = options.offscreen_indicators;
+    if max == 0 || viewport.width == 0 || viewport.height == 0 {
         return;
     }
-    let max_len = word.trail_len.min(TRAIL_LEN);
-    for i in 0..max_len {
-        // リングバッファを最新から古い順にアクセス
-        let idx = (word.trail_head + TRAIL_LEN - i) % TRAIL_LEN;
-        let pos = word.trail[idx];
-        let sx = ((pos.x - camera.pos.x) * camera.zoom + half_w).round() as i32;
-        let sy = ((pos.y - camera.pos.y) * camera.zoom + half_h).round() as i32;
-        if sx < 0 || sy < 0 || sx >= viewport.width as i32 || sy >= viewport.height as i32 {
-            continue;
-        }
-        let age = i as f32 / max_len as f32;
-        let ch = if age < 0.4 { '·' } else { '.' };
-        let mass = word.mass_visible * (0.3 * (1.0 - age));
-        frame.set(sx as u16, sy as u16, ch, mass, ColorId::Trail);
+    let (width, height) = (viewport.width as i32, viewport.height as i32);
+    let mut targets: Vec<(bool, f32, &WordSnapshot)> = snapshot
+        .iter()
+        .filter_map(|word| {
+            let (x, y) = word_anchor(word, camera, viewport);
+            if (0..width).contains(&x) && (0..height).contains(&y) {
+                return None;
+            }
+            let focus = focus_word_id == Some(word.id);
+            if !focus && word.mass_visible < options.offscreen_min_mass {
+                return None;
+            }
+            let dist_sq = (word.pos - camera.pos).length_sq().max(1.0);
+            Some((focus, word.mass_visible / dist_sq, word))
+        })
+        .collect();
+    targets.sort_by(|a, b| {
+        b.0.cmp(&a.0)
+            .then(b.1.total_cmp(&a.1))
+            .then(a.2.id.cmp(&b.2.id))
+    });
+
+    let (cx, cy) = ((width - 1) as f32 * 0.5, (height - 1) as f32 * 0.5);
+    for &(focus, _, word) in targets.iter().take(max) {
+        let (sx, sy) = camera.world_to_screen_f32(word.pos, viewport);
+        let (dx, dy) = (sx - cx, sy - cy);
+        let reach = |half: f32, d: f32| if d == 0.0 { f32::INFINITY } else { half / d.abs() };
+        let t = reach(cx, dx).min(reach(cy, dy));
+        let x = ((cx + dx * t).round() as i32).clamp(0, width - 1);
+        let y = ((cy + dy * t).round() as i32).clamp(0, height - 1);
+        // 画面の y は下向きなので上を正にして角度を取る
+        let angle = (-dy).atan2(dx);
+        let sector = (angle / std::f32::consts::FRAC_PI_4).round() as i32;
+        let glyph = OFFSCREEN_ARROWS[sector.rem_euclid(8) as usize];
+        let color = if focus { ColorId::Red } else { base_color(word, options) };
+        frame.set(x as u16, y as u16, glyph, OFFSCREEN_PRIORITY, color);
     }
 }
 
-fn word_color(word: &WordSnapshot) -> ColorId {
-    let dust_ratio = if word.mass_total > 0.0 {
-        (word.mass_dust / word.mass_total).min(1.0)
-    } else {
-        0.0
-    };
-    let speed = word.vel.length();
-    if dust_ratio > 0.6 {
-        ColorId::Gray
-    } else if speed > 14.0 {
-        ColorId::Cyan
-    } else if word.mass_visible > 20.0 {
-        ColorId::Yellow
-    } else if word.mass_visible > 10.0 {
-        ColorId::Magenta
-    } else if word.mass_visible > 6.0 {
-        ColorId::Blue
-    } else {
-        ColorId::White
+// 合体の記録から進み具合 t（0..1）の幻影を out に足す。元の語は合体語の現在位置 target へ
+// 減速しながら寄っていき、streaks には各幻影から target への線を足す
+pub fn merge_ghosts(
+    record: &MergeRecord,
+    target: Vec2,
+    t: f32,
+    out: &mut Vec<WordSnapshot>,
+    streaks: &mut Vec<(Vec2, Vec2)>,
+) {
+    let t = t.clamp(0.0, 1.0);
+    let eased = 1.0 - (1.0 - t) * (1.0 - t);
+    for source in &record.sources {
+        let pos = source.pos + (target - source.pos) * eased;
+        out.push(WordSnapshot {
+            pos,
+            trail_len: 0,
+            merge_pending: false,
+            ..*source
+        });
+        streaks.push((pos, target));
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    mod camera {
-        use super::*;
+// 語を描き始めるセル（物理座標をそのまま写したもの）
+fn word_anchor(word: &WordSnapshot, camera: &Camera, viewport: Viewport) -> (i32, i32) {
+    camera.world_to_screen(word.pos, viewport)
+}
 
-        #[test]
-        fn default_camera_at_origin() {
-            let camera = Camera::default();
-            assert_eq!(camera.pos, Vec2::ZERO);
-            assert_eq!(camera.zoom, 1.0);
+// snapshot と同じ並びで、各語の開始セルを何行下へずらすか。
+// フォーカス語、続いて可視質量の大きい順に開始セルを確保し、埋まっていれば1行ずつ下を探す。
+// max 行ずらしても空かなければ元の位置に描き、優先度による上書きに任せる。
+fn deconflict_shifts(
+    snapshot: &[WordSnapshot],
+    focus_word_id: Option<WordId>,
+    camera: &Camera,
+    viewport: Viewport,
+    options: &DrawOptions,
+) -> Vec<i32> {
+    let max = options.deconflict_shifts as i32;
+    if max == 0 {
+        return Vec::new();
+    }
+    let mut order: Vec<usize> = (0..snapshot.len()).collect();
+    order.sort_by(|&a, &b| {
+        let (wa, wb) = (&snapshot[a], &snapshot[b]);
+        let focus = |w: &WordSnapshot| focus_word_id == Some(w.id);
+        focus(wb)
+            .cmp(&focus(wa))
+            .then(wb.mass_visible.total_cmp(&wa.mass_visible))
+            .then(wa.id.cmp(&wb.id))
+    });
+    let mut taken: HashSet<(i32, i32)> = HashSet::with_capacity(snapshot.len());
+    let mut shifts = vec![0; snapshot.len()];
+    for i in order {
+        let (x, y) = word_anchor(&snapshot[i], camera, viewport);
+        if let Some(shift) = (0..=max).find(|&shift| !taken.contains(&(x, y + shift))) {
+            taken.insert((x, y + shift));
+            shifts[i] = shift;
         }
     }
+    shifts
+}
 
-    mod framebuffer {
-        use super::*;
+fn draw_word(
+    word: &WordSnapshot,
+    (sx, sy): (i32, i32),
+    priority: f32,
+    color: ColorId,
+    viewport: Viewport,
+    options: &DrawOptions,
+    frame: &mut FrameBuffer,
+) {
+    if options.vertical_text {
+        draw_word_vertical(word, (sx, sy), priority, color, viewport, frame);
+        return;
+    }
+    // 各行は sx から始まり、どの行も語全体の幅を超えないので、横に外れた語は並べる前に捨てる
+    if sx >= viewport.width as i32 || sx + drawn_width(word) <= 0 {
+        return;
+    }
+    let (text, lines) = word_layout(word, options);
+    let top = sy - (lines.len() as i32 - 1) / 2;
+    for (row, &(start, end)) in lines.iter().enumerate() {
+        let y = top + row as i32;
+        if y < 0 || y >= viewport.height as i32 {
+            continue;
+        }
+        let uy = y as u16;
+        let x = put_chars(frame, (sx, uy), &text[start..end], priority, color, viewport.width);
+        let last = row + 1 == lines.len();
+        if last && word.truncated && x >= 0 && x < viewport.width as i32 {
+            frame.set(x as u16, uy, ELLIPSIS, priority, color);
+        }
+    }
+}
 
-        mod new {
-            use super::*;
+// LOD で簡略化した語の1文字。全角なら右隣のセルも埋める
+fn draw_glyph(
+    ch: char,
+    (sx, sy): (i32, i32),
+    priority: f32,
+    color: ColorId,
+    viewport: Viewport,
+    frame: &mut FrameBuffer,
+) {
+    let width = char_cell_width(ch) as i32;
+    if sx < 0 || sy < 0 || sx + width > viewport.width as i32 || sy >= viewport.height as i32 {
+        return;
+    }
+    frame.set(sx as u16, sy as u16, ch, priority, color);
+    if width == 2 {
+        frame.set(sx as u16 + 1, sy as u16, WIDE_CONTINUATION, priority, color);
+    }
+}
 
-            #[test]
-            fn creates_with_correct_dimensions() {
-                let fb = FrameBuffer::new(80, 24);
-                assert_eq!(fb.width(), 80);
-                assert_eq!(fb.height(), 24);
+// 縦書き。省略記号も含めて1文字1行で、画面の下端を越えた分は描かない
+fn draw_word_vertical(
+    word: &WordSnapshot,
+    (sx, sy): (i32, i32),
+    priority: f32,
+    color: ColorId,
+    viewport: Viewport,
+    frame: &mut FrameBuffer,
+) {
+    let ellipsis = word.truncated.then_some(ELLIPSIS);
+    let mut y = sy - 1;
+    for ch in drawn_text(word).iter().copied().chain(ellipsis) {
+        // 幅 0 の文字は1行を取らず、直前の行の文字に重ねる
+        if char_cell_width(ch) == 0 {
+            if sx >= 0 && y >= 0 {
+                frame.set_mark(sx as u16, y as u16, ch, priority);
             }
+            continue;
+        }
+        y += 1;
+        if y >= viewport.height as i32 {
+            break;
+        }
+        draw_glyph(ch, (sx, y), priority, color, viewport, frame);
+    }
+}
 
-            #[test]
-            fn zero_dimensions_creates_empty_buffer() {
-                let fb = FrameBuffer::new(0, 0);
-                assert_eq!(fb.width(), 0);
-                assert_eq!(fb.height(), 0);
-            }
+// 描く文字列。末尾の '-' は省略記号に置き換えるので除く
+fn drawn_text(word: &WordSnapshot) -> &[char] {
+    let mut text_len = word.text_len.min(TEXT_MAX_DRAW);
+    if word.truncated && text_len > 0 && word.text[text_len - 1] == '-' {
+        text_len -= 1;
+    }
+    &word.text[..text_len]
+}
+
+// 描画幅が max_cells を超える語を、省略記号1セルぶんを残して文字単位で切り詰めた写し。
+// 収まる語（と max_cells が 0 のとき）は写さずにそのまま返す
+fn capped_word(word: &WordSnapshot, max_cells: usize) -> Cow<'_, WordSnapshot> {
+    if max_cells == 0 || drawn_width(word) as usize <= max_cells {
+        return Cow::Borrowed(word);
+    }
+    let mut len = 0;
+    let mut width = 0;
+    for &ch in &word.text[..word.text_len] {
+        let ch_width = char_cell_width(ch);
+        if width + ch_width + 1 > max_cells {
+            break;
         }
+        width += ch_width;
+        len += 1;
+    }
+    let mut capped = *word;
+    capped.text[len..].fill(' ');
+    capped.text_len = len;
+    capped.truncated = true;
+    capped.display_width = width as u16;
+    Cow::Owned(capped)
+}
 
-        mod resize {
-            use super::*;
+// 1行に並べたときの描画幅。drawn_text で除いた '-' を引き、省略記号を足す
+fn drawn_width(word: &WordSnapshot) -> i32 {
+    let dropped = drawn_text(word).len() < word.text_len.min(TEXT_MAX_DRAW);
+    word.display_width as i32 - dropped as i32 + word.truncated as i32
+}
 
-            #[test]
-            fn changes_dimensions() {
-                let mut fb = FrameBuffer::new(10, 10);
-                fb.resize(20, 15);
-                assert_eq!(fb.width(), 20);
-                assert_eq!(fb.height(), 15);
-            }
+// 描く文字列と折り返し後の各行の範囲。複数行は語の位置を中心に上下へ広げる。
+fn word_layout<'w>(
+    word: &'w WordSnapshot,
+    options: &DrawOptions,
+) -> (&'w [char], Vec<(usize, usize)>) {
+    let text = drawn_text(word);
+    let text_len = text.len();
+    let lines = if options.word_wrap {
+        wrap_lines(text, config::WORD_WRAP_WIDTH)
+    } else {
+        vec![(0, text_len)]
+    };
+    (text, lines)
+}
 
-            #[test]
-            fn clears_cells_on_resize() {
-                let mut fb = FrameBuffer::new(10, 10);
-                fb.resize(10, 10);
-                let cell = fb.get(0, 0);
-                assert_eq!(cell.ch, ' ');
-            }
+// 語の描画範囲（全角幅と省略記号を含む）を1セル外側から囲む。画面外の部分は描かない
+fn draw_focus_frame(
+    word: &WordSnapshot,
+    (sx, sy): (i32, i32),
+    viewport: Viewport,
+    options: &DrawOptions,
+    frame: &mut FrameBuffer,
+) {
+    let (text, lines) = word_layout(word, options);
+    let (width, rows, top) = if options.vertical_text {
+        let width = text.iter().map(|&ch| char_cell_width(ch) as i32).max().unwrap_or(0);
+        let glyphs = text.iter().filter(|&&ch| char_cell_width(ch) > 0).count();
+        let rows = glyphs as i32 + word.truncated as i32;
+        (width.max(word.truncated as i32), rows, sy - 1)
+    } else if lines.len() == 1 {
+        (drawn_width(word), 1, sy - 1)
+    } else {
+        let width = lines
+            .iter()
+            .enumerate()
+            .map(|(row, &(start, end))| {
+                let cells: usize = text[start..end].iter().map(|&ch| char_cell_width(ch)).sum();
+                let ellipsis = row + 1 == lines.len() && word.truncated;
+                cells as i32 + ellipsis as i32
+            })
+            .max()
+            .unwrap_or(0);
+        (width, lines.len() as i32, sy - (lines.len() as i32 - 1) / 2 - 1)
+    };
+    let bottom = top + rows + 1;
+    let (left, right) = (sx - 1, sx + width);
+    let [top_left, top_right, bottom_left, bottom_right, horizontal, vertical] =
+        FOCUS_FRAME_GLYPHS;
+
+    let mut put = |x: i32, y: i32, ch: char| {
+        if x >= 0 && y >= 0 && x < viewport.width as i32 && y < viewport.height as i32 {
+            frame.set(x as u16, y as u16, ch, FOCUS_FRAME_PRIORITY, ColorId::Red);
         }
+    };
+    for x in sx..right {
+        put(x, top, horizontal);
+        put(x, bottom, horizontal);
+    }
+    for y in top + 1..bottom {
+        put(left, y, vertical);
+        put(right, y, vertical);
+    }
+    put(left, top, top_left);
+    put(right, top, top_right);
+    put(left, bottom, bottom_left);
+    put(right, bottom, bottom_right);
+}
 
-        mod clear {
-            use super::*;
+// ワールド全域（WORLD_HALF_*）が width x height に収まるカメラ
+pub fn overview_camera(width: u16, height: u16) -> Camera {
+    let zoom_x = width as f32 / (2.0 * config::WORLD_HALF_WIDTH);
+    let zoom_y = height as f32 * config::CELL_ASPECT / (2.0 * config::WORLD_HALF_HEIGHT);
+    Camera {
+        pos: Vec2::ZERO,
+        zoom: zoom_x.min(zoom_y).max(1.0e-3),
+        aspect: config::CELL_ASPECT,
+    }
+}
 
-            #[test]
-            fn resets_all_cells_to_space() {
-                let mut fb = FrameBuffer::new(10, 10);
-                fb.clear();
-                for y in 0..10 {
+// 全域を1枚に描く。重い語から順に1パスで置き、語全体が空きセルに収まらなければ
+// 先頭1文字だけを近くの空きセルへずらして置くので、空きがある限り全可視語が残る。
+pub fn render_world_overview(snapshot: &[WordSnapshot], width: u16, height: u16) -> FrameBuffer {
+    let mut frame = FrameBuffer::new(width, height);
+    if width == 0 || height == 0 {
+        return frame;
+    }
+    let camera = overview_camera(width, height);
+    let viewport = Viewport { width, height };
+    let search_max = width.max(height) as i32;
+
+    let mut order: Vec<&WordSnapshot> = snapshot.iter().collect();
+    order.sort_by(|a, b| b.mass_visible.total_cmp(&a.mass_visible));
+    for word in order {
+        let (sx, sy) = camera.world_to_screen(word.pos, viewport);
+        let sx = sx.clamp(0, width as i32 - 1);
+        let sy = sy.clamp(0, height as i32 - 1);
+        let text = &word.text[..word.text_len.min(TEXT_MAX_DRAW)];
+        let color = word_color(word);
+
+        let text_width = word.display_width as i32;
+        if text_width > 0 && (0..text_width).all(|dx| frame.is_free(sx + dx, sy)) {
+            put_run(&mut frame, sx, sy, text, word.mass_visible, color);
+            continue;
+        }
+        let Some(&first) = text.first() else {
+            continue;
+        };
+        let first_width = char_cell_width(first) as i32;
+        'search: for r in 0..=search_max {
+            for dy in -r..=r {
+                for dx in -r..=r {
+                    if dx.abs().max(dy.abs()) != r {
+                        continue;
+                    }
+                    let (x, y) = (sx + dx, sy + dy);
+                    if (0..first_width).all(|w| frame.is_free(x + w, y)) {
+                        put_run(&mut frame, x, y, &[first], word.mass_visible, color);
+                        break 'search;
+                    }
+                }
+            }
+        }
+    }
+    frame
+}
+
+fn put_run(frame: &mut FrameBuffer, x: i32, y: i32, text: &[char], priority: f32, color: ColorId) {
+    if y >= 0 {
+        let width = frame.width();
+        put_chars(frame, (x, y as u16), text, priority, color, width);
+    }
+}
+
+// 1行ぶんを左から並べ、次の桁を返す。幅 0 の文字は直前の文字のセルに重ねる。
+// 画面外にはみ出した文字と、それに続く幅 0 の文字は描かない
+fn put_chars(
+    frame: &mut FrameBuffer,
+    (x, y): (i32, u16),
+    text: &[char],
+    priority: f32,
+    color: ColorId,
+    limit: u16,
+) -> i32 {
+    let mut x = x;
+    let mut base: Option<u16> = None;
+    for &ch in text {
+        let width = char_cell_width(ch) as i32;
+        if width == 0 {
+            if let Some(bx) = base {
+                frame.set_mark(bx, y, ch, priority);
+            }
+            continue;
+        }
+        base = None;
+        if x >= 0 && x + width <= limit as i32 {
+            frame.set(x as u16, y, ch, priority, color);
+            if width == 2 {
+                frame.set(x as u16 + 1, y, WIDE_CONTINUATION, priority, color);
+            }
+            base = Some(x as u16);
+        }
+        x += width;
+    }
+    x
+}
+
+// コンポーネント境界（'-'）で折り返し、各行の表示幅を max_width セル以下に収める。
+// 1コンポーネントだけで幅を超える場合はその中で文字単位に折る。
+// 戻り値は text 上の各行の [start, end) 範囲。行頭・行末の区切りは含めない。
+fn wrap_lines(text: &[char], max_width: usize) -> Vec<(usize, usize)> {
+    let max_width = max_width.max(2);
+    let mut lines = Vec::new();
+    let mut line_start = 0;
+    let mut line_width = 0;
+    let mut line_end = 0;
+    let mut comp_start = 0;
+    while comp_start <= text.len() {
+        let comp_end = text[comp_start..]
+            .iter()
+            .position(|&ch| ch == '-')
+            .map_or(text.len(), |offset| comp_start + offset);
+        let comp_width: usize = text[comp_start..comp_end]
+            .iter()
+            .map(|&ch| char_cell_width(ch))
+            .sum();
+        if line_width > 0 && line_width + 1 + comp_width > max_width {
+            lines.push((line_start, line_end));
+            line_width = 0;
+        }
+        if line_width == 0 {
+            line_start = comp_start;
+        } else {
+            line_width += 1;
+        }
+        for (offset, &ch) in text[comp_start..comp_end].iter().enumerate() {
+            let width = char_cell_width(ch);
+            if line_width > 0 && line_width + width > max_width {
+                lines.push((line_start, comp_start + offset));
+                line_start = comp_start + offset;
+                line_width = 0;
+            }
+            line_width += width;
+        }
+        line_end = comp_end;
+        comp_start = comp_end + 1;
+    }
+    if line_end > line_start || lines.is_empty() {
+        lines.push((line_start, line_end));
+    }
+    lines
+}
+
+// 語の半径が覆うセルに可視質量を加算する
+fn accumulate_density(
+    word: &WordSnapshot,
+    camera: &Camera,
+    viewport: Viewport,
+    frame: &mut FrameBuffer,
+) {
+    let (cx, cy) = camera.world_to_screen_f32(word.pos, viewport);
+    let rx = (word.radius * camera.zoom).max(0.5);
+    let ry = (word.radius * camera.zoom / camera.aspect).max(0.5);
+    let (x0, x1) = ((cx - rx).floor() as i32, (cx + rx).ceil() as i32);
+    let (y0, y1) = ((cy - ry).floor() as i32, (cy + ry).ceil() as i32);
+    for y in y0..=y1 {
+        for x in x0..=x1 {
+            let dx = (x as f32 - cx) / rx;
+            let dy = (y as f32 - cy) / ry;
+            if dx * dx + dy * dy <= 1.0 {
+                frame.add_density(x, y, word.mass_visible);
+            }
+        }
+    }
+}
+
+fn draw_trail(
+    word: &WordSnapshot,
+    stride: usize,
+    camera: &Camera,
+    viewport: Viewport,
+    frame: &mut FrameBuffer,
+) {
+    if word.trail_len == 0 || stride == 0 {
+        return;
+    }
+    let max_len = word.trail_len.min(TRAIL_LEN);
+    for i in (0..max_len).step_by(stride) {
+        // リングバッファを最新から古い順にアクセス
+        let idx = (word.trail_head + TRAIL_LEN - i) % TRAIL_LEN;
+        let (sx, sy) = camera.world_to_screen(word.trail[idx], viewport);
+        if sx < 0 || sy < 0 || sx >= viewport.width as i32 || sy >= viewport.height as i32 {
+            continue;
+        }
+        let age = i as f32 / max_len as f32;
+        let ch = if age < 0.4 { '·' } else { '.' };
+        let mass = word.mass_visible * (0.3 * (1.0 - age));
+        frame.set(sx as u16, sy as u16, ch, mass, ColorId::Trail);
+    }
+}
+
+fn draw_circle(
+    center: Vec2,
+    radius: f32,
+    color: ColorId,
+    camera: &Camera,
+    viewport: Viewport,
+    frame: &mut FrameBuffer,
+) {
+    let ring = RingStyle { glyph: OVERLAY_GLYPH, priority: PREDICTION_PRIORITY, color };
+    draw_ring(center, radius, ring, camera, viewport, frame);
+}
+
+#[derive(Clone, Copy, Debug)]
+struct RingStyle {
+    glyph: char,
+    priority: f32,
+    color: ColorId,
+}
+
+// ワールド座標の円。画面上の周長に見合う点数で、ズームとセルの縦横比を反映して描く。
+fn draw_ring(
+    center: Vec2,
+    radius: f32,
+    style: RingStyle,
+    camera: &Camera,
+    viewport: Viewport,
+    frame: &mut FrameBuffer,
+) {
+    let points = ((std::f32::consts::TAU * radius * camera.zoom).ceil() as usize).clamp(16, 4096);
+    for i in 0..points {
+        let angle = i as f32 / points as f32 * std::f32::consts::TAU;
+        let pos = center + Vec2::new(angle.cos(), angle.sin()) * radius;
+        let (sx, sy) = camera.world_to_screen(pos, viewport);
+        if sx < 0 || sy < 0 || sx >= viewport.width as i32 || sy >= viewport.height as i32 {
+            continue;
+        }
+        frame.set(sx as u16, sy as u16, style.glyph, style.priority, style.color);
+    }
+}
+
+// cell_size 刻みのセル境界と、counts なら各セルの語数（99 で頭打ち）。
+// 線の間隔が2セルを切るほど縮小したときは描かない。
+fn draw_heatmap(map: &ActivityMap, camera: &Camera, viewport: Viewport, frame: &mut FrameBuffer) {
+    let max = map
+        .cells
+        .values()
+        .map(|cell| cell.merges + cell.splits)
+        .fold(0.0_f32, f32::max);
+    if max <= 0.0 || map.cell_size <= 0.0 {
+        return;
+    }
+    for y in 0..viewport.height {
+        for x in 0..viewport.width {
+            let world = camera.screen_to_world(x as f32 + 0.5, y as f32 + 0.5, viewport);
+            let key = (
+                (world.x / map.cell_size).floor() as i32,
+                (world.y / map.cell_size).floor() as i32,
+            );
+            let Some(cell) = map.cells.get(&key) else {
+                continue;
+            };
+            let ratio = (cell.merges + cell.splits) / max;
+            if ratio < HEATMAP_MIN_RATIO {
+                continue;
+            }
+            let levels = DENSITY_GLYPHS.len();
+            let level = ((ratio * levels as f32).ceil() as usize).clamp(1, levels) - 1;
+            let color = if cell.merges >= cell.splits { ColorId::Yellow } else { ColorId::Cyan };
+            frame.set(x, y, DENSITY_GLYPHS[level], HEATMAP_PRIORITY, color);
+        }
+    }
+}
+
+fn draw_grid(
+    snapshot: &[WordSnapshot],
+    cell_size: f32,
+    counts: bool,
+    camera: &Camera,
+    viewport: Viewport,
+    frame: &mut FrameBuffer,
+) {
+    let scale_x = camera.zoom;
+    let scale_y = camera.zoom / camera.aspect;
+    if cell_size * scale_x < 2.0 || cell_size * scale_y < 2.0 {
+        return;
+    }
+    let (width, height) = (viewport.width as i32, viewport.height as i32);
+    let to_sx = |wx: f32| camera.world_to_screen(Vec2::new(wx, camera.pos.y), viewport).0;
+    let to_sy = |wy: f32| camera.world_to_screen(Vec2::new(camera.pos.x, wy), viewport).1;
+    let min = camera.screen_to_world(0.0, 0.0, viewport);
+    let max = camera.screen_to_world(width as f32, height as f32, viewport);
+    let lines = |lo: f32, hi: f32, to_screen: &dyn Fn(f32) -> i32, limit: i32| {
+        let first = (lo / cell_size).floor() as i32;
+        let last = (hi / cell_size).ceil() as i32;
+        (first..=last)
+            .map(|k| to_screen(k as f32 * cell_size))
+            .filter(|&s| (0..limit).contains(&s))
+            .collect::<Vec<i32>>()
+    };
+    let columns = lines(min.x, max.x, &to_sx, width);
+    let rows = lines(min.y, max.y, &to_sy, height);
+
+    let [vertical, horizontal, cross] = GRID_GLYPHS;
+    for &x in &columns {
+        for y in 0..height {
+            frame.set(x as u16, y as u16, vertical, GRID_PRIORITY, ColorId::Shade);
+        }
+    }
+    for &y in &rows {
+        for x in 0..width {
+            let ch = if columns.contains(&x) { cross } else { horizontal };
+            frame.set(x as u16, y as u16, ch, GRID_PRIORITY, ColorId::Shade);
+        }
+    }
+
+    if !counts {
+        return;
+    }
+    let mut per_cell: HashMap<(i32, i32), usize> = HashMap::new();
+    for word in snapshot {
+        let key = (
+            (word.pos.x / cell_size).floor() as i32,
+            (word.pos.y / cell_size).floor() as i32,
+        );
+        *per_cell.entry(key).or_insert(0) += 1;
+    }
+    for ((kx, ky), count) in per_cell {
+        let corner = Vec2::new(kx as f32, ky as f32) * cell_size;
+        let (sx, sy) = camera.world_to_screen(corner, viewport);
+        let (sx, sy) = (sx + 1, sy + 1);
+        if sy < 0 || sy >= height {
+            continue;
+        }
+        for (i, digit) in count.min(99).to_string().chars().enumerate() {
+            let x = sx + i as i32;
+            if (0..width).contains(&x) {
+                frame.set(x as u16, sy as u16, digit, GRID_PRIORITY, ColorId::Gray);
+            }
+        }
+    }
+}
+
+// 軌跡とは別のグリフ・色で、語より下に薄く描く
+fn draw_prediction(points: &[Vec2], camera: &Camera, viewport: Viewport, frame: &mut FrameBuffer) {
+    for &pos in points {
+        let (sx, sy) = camera.world_to_screen(pos, viewport);
+        if sx < 0 || sy < 0 || sx >= viewport.width as i32 || sy >= viewport.height as i32 {
+            continue;
+        }
+        frame.set(sx as u16, sy as u16, PREDICTION_GLYPH, PREDICTION_PRIORITY, ColorId::Prediction);
+    }
+}
+
+// 両端の語のセルは語で上書きされるので、端まで描いてよい
+fn draw_segment(
+    (a, b): (Vec2, Vec2),
+    glyph: char,
+    color: ColorId,
+    camera: &Camera,
+    viewport: Viewport,
+    frame: &mut FrameBuffer,
+) {
+    let (ax, ay) = camera.world_to_screen_f32(a, viewport);
+    let (bx, by) = camera.world_to_screen_f32(b, viewport);
+    let steps = (bx - ax).abs().max((by - ay).abs()).ceil().max(1.0);
+    // 画面外へ長く伸びた線で無駄に回らないよう、画面の対角線程度で打ち切る
+    let limit = (viewport.width as f32 + viewport.height as f32) * 2.0;
+    let steps = steps.min(limit) as i32;
+    for i in 0..=steps {
+        let t = i as f32 / steps as f32;
+        let sx = (ax + (bx - ax) * t).floor() as i32;
+        let sy = (ay + (by - ay) * t).floor() as i32;
+        if sx < 0 || sy < 0 || sx >= viewport.width as i32 || sy >= viewport.height as i32 {
+            continue;
+        }
+        frame.set(sx as u16, sy as u16, glyph, PREDICTION_PRIORITY, color);
+    }
+}
+
+// color_fn が決めた色、無ければ color_mode の既定色
+fn base_color(word: &WordSnapshot, options: &DrawOptions) -> ColorId {
+    if let Some(color) = options.color_fn.and_then(|color_fn| color_fn(word)) {
+        return color;
+    }
+    match options.color_mode {
+        WordColorMode::Discrete => word_color(word),
+        WordColorMode::Continuous => word_color_continuous(word),
+    }
+}
+
+fn word_color(word: &WordSnapshot) -> ColorId {
+    let dust_ratio = if word.mass_total > 0.0 {
+        (word.mass_dust / word.mass_total).min(1.0)
+    } else {
+        0.0
+    };
+    let speed = word.vel.length();
+    if dust_ratio > 0.6 {
+        ColorId::Gray
+    } else if speed > 14.0 {
+        ColorId::Cyan
+    } else if word.mass_visible > 20.0 {
+        ColorId::Yellow
+    } else if word.mass_visible > 10.0 {
+        ColorId::Magenta
+    } else if word.mass_visible > 6.0 {
+        ColorId::Blue
+    } else {
+        ColorId::White
+    }
+}
+
+// 軽い語は淡い青、質量とともに紫を経て黄へ、速い語はシアンへ寄せる。
+// 塵比率が高いほど彩度・明度を落としてグレーに近づける。
+pub fn word_color_continuous(word: &WordSnapshot) -> ColorId {
+    let dust_ratio = if word.mass_total > 0.0 {
+        (word.mass_dust / word.mass_total).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+    let mass_t = ((1.0 + word.mass_visible.max(0.0)).ln() / (1.0 + config::COLOR_MASS_REF).ln())
+        .clamp(0.0, 1.0);
+    let speed_t = (word.vel.length() / config::COLOR_SPEED_REF).clamp(0.0, 1.0);
+
+    // 230°(青) -> 300°(紫) -> 420°=60°(黄)。速度はシアンとの RGB 補間で連続的に混ぜる。
+    let saturation = (0.2 + 0.7 * mass_t) * (1.0 - dust_ratio);
+    let value = 1.0 - 0.45 * dust_ratio;
+    let mass_rgb = hsv_to_rgb(230.0 + 190.0 * mass_t, saturation, value);
+    let fast_rgb = hsv_to_rgb(180.0, 0.9 * (1.0 - dust_ratio), value);
+    let t = speed_t * speed_t;
+    let mix = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * t).round() as u8;
+    ColorId::Rgb(
+        mix(mass_rgb.0, fast_rgb.0),
+        mix(mass_rgb.1, fast_rgb.1),
+        mix(mass_rgb.2, fast_rgb.2),
+    )
+}
+
+fn hsv_to_rgb(hue: f32, saturation: f32, value: f32) -> (u8, u8, u8) {
+    let h = hue.rem_euclid(360.0) / 60.0;
+    let c = value * saturation;
+    let x = c * (1.0 - (h % 2.0 - 1.0).abs());
+    let (r, g, b) = match h as u32 {
+        0 => (c, x, 0.0),
+        1 => (x, c, 0.0),
+        2 => (0.0, c, x),
+        3 => (0.0, x, c),
+        4 => (x, 0.0, c),
+        _ => (c, 0.0, x),
+    };
+    let m = value - c;
+    let to_u8 = |v: f32| ((v + m).clamp(0.0, 1.0) * 255.0).round() as u8;
+    (to_u8(r), to_u8(g), to_u8(b))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::text_display_width;
+
+    mod camera {
+        use super::*;
+
+        #[test]
+        fn default_camera_at_origin() {
+            let camera = Camera::default();
+            assert_eq!(camera.pos, Vec2::ZERO);
+            assert_eq!(camera.zoom, 1.0);
+            assert_eq!(camera.aspect, config::CELL_ASPECT);
+        }
+
+        #[test]
+        fn screen_center_maps_to_camera_pos() {
+            let camera = Camera {
+                pos: Vec2::new(3.0, -2.0),
+                zoom: 2.0,
+                aspect: 2.0,
+            };
+            let viewport = Viewport { width: 80, height: 24 };
+            assert_eq!(camera.screen_to_world(40.0, 12.0, viewport), camera.pos);
+        }
+
+        #[test]
+        fn aspect_stretches_vertical_axis() {
+            let camera = Camera {
+                pos: Vec2::ZERO,
+                zoom: 1.0,
+                aspect: 2.0,
+            };
+            let viewport = Viewport { width: 80, height: 24 };
+            let world = camera.screen_to_world(40.0, 14.0, viewport);
+            // 2行下は世界座標で4単位下
+            assert!((world.y - 4.0).abs() < 1e-6);
+        }
+
+        #[test]
+        fn world_to_screen_round_trips() {
+            let viewport = Viewport { width: 81, height: 25 };
+            for (pos, zoom, aspect) in [
+                (Vec2::ZERO, 1.0, 2.0),
+                (Vec2::new(-37.5, 12.25), 0.35, 2.0),
+                (Vec2::new(120.0, -80.0), 3.0, 1.5),
+            ] {
+                let camera = Camera { pos, zoom, aspect };
+                for world in [pos, pos + Vec2::new(10.0, -6.0), Vec2::new(-200.0, 150.0)] {
+                    let (x, y) = camera.world_to_screen_f32(world, viewport);
+                    let back = camera.screen_to_world(x, y, viewport);
+                    assert!((back - world).length() < 1e-3, "{world:?} -> {back:?}");
+                }
+                // セル座標への丸めの誤差は半セル分まで
+                let (sx, sy) = camera.world_to_screen(pos + Vec2::new(3.3, 4.4), viewport);
+                let back = camera.screen_to_world(sx as f32, sy as f32, viewport);
+                let err = back - (pos + Vec2::new(3.3, 4.4));
+                assert!(err.x.abs() <= 0.5 / zoom + 1e-4);
+                assert!(err.y.abs() <= 0.5 * aspect / zoom + 1e-4);
+            }
+        }
+
+        #[test]
+        fn camera_position_maps_to_viewport_center() {
+            let camera = Camera {
+                pos: Vec2::new(5.0, -3.0),
+                zoom: 2.0,
+                aspect: 2.0,
+            };
+            let viewport = Viewport { width: 80, height: 24 };
+            assert_eq!(camera.world_to_screen(camera.pos, viewport), (40, 12));
+            assert_eq!(camera.world_to_screen(Vec2::new(6.0, 1.0), viewport), (42, 16));
+        }
+    }
+
+    mod framebuffer {
+        use super::*;
+
+        mod new {
+            use super::*;
+
+            #[test]
+            fn creates_with_correct_dimensions() {
+                let fb = FrameBuffer::new(80, 24);
+                assert_eq!(fb.width(), 80);
+                assert_eq!(fb.height(), 24);
+            }
+
+            #[test]
+            fn zero_dimensions_creates_empty_buffer() {
+                let fb = FrameBuffer::new(0, 0);
+                assert_eq!(fb.width(), 0);
+                assert_eq!(fb.height(), 0);
+            }
+        }
+
+        mod resize {
+            use super::*;
+
+            #[test]
+            fn changes_dimensions() {
+                let mut fb = FrameBuffer::new(10, 10);
+                fb.resize(20, 15);
+                assert_eq!(fb.width(), 20);
+                assert_eq!(fb.height(), 15);
+            }
+
+            #[test]
+            fn clears_cells_on_resize() {
+                let mut fb = FrameBuffer::new(10, 10);
+                fb.resize(10, 10);
+                let cell = fb.get(0, 0);
+                assert_eq!(cell.ch, ' ');
+            }
+        }
+
+        mod clear {
+            use super::*;
+
+            #[test]
+            fn resets_all_cells_to_space() {
+                let mut fb = FrameBuffer::new(10, 10);
+                fb.clear();
+                for y in 0..10 {
                     for x in 0..10 {
                         let cell = fb.get(x, y);
                         assert_eq!(cell.ch, ' ');
@@ -324,6 +1467,8 @@ mod tests {
                 id: 1,
                 text: [' '; TEXT_MAX_DRAW],
                 text_len: 0,
+                truncated: false,
+                display_width: 0,
                 pos: Vec2::ZERO,
                 radius: 1.0,
                 mass_visible,
@@ -333,6 +1478,10 @@ mod tests {
                 trail: [Vec2::ZERO; TRAIL_LEN],
                 trail_len: 0,
                 trail_head: 0,
+                merge_pending: false,
+                merge_count: 0,
+                split_count: 0,
+                age: 0.0,
             }
         }
 
@@ -380,6 +1529,96 @@ mod tests {
         }
     }
 
+    mod word_color_continuous_fn {
+        use super::*;
+
+        fn rgb(mass_visible: f32, mass_dust: f32, vel: Vec2) -> (i32, i32, i32) {
+            let snapshot = snapshot_with(mass_visible, mass_dust, vel);
+            match word_color_continuous(&snapshot) {
+                ColorId::Rgb(r, g, b) => (r as i32, g as i32, b as i32),
+                other => panic!("expected Rgb, got {other:?}"),
+            }
+        }
+
+        fn snapshot_with(mass_visible: f32, mass_dust: f32, vel: Vec2) -> WordSnapshot {
+            WordSnapshot {
+                id: 1,
+                text: [' '; TEXT_MAX_DRAW],
+                text_len: 0,
+                truncated: false,
+                display_width: 0,
+                pos: Vec2::ZERO,
+                radius: 1.0,
+                mass_visible,
+                mass_total: mass_visible + mass_dust,
+                mass_dust,
+                vel,
+                trail: [Vec2::ZERO; TRAIL_LEN],
+                trail_len: 0,
+                trail_head: 0,
+                merge_pending: false,
+                merge_count: 0,
+                split_count: 0,
+                age: 0.0,
+            }
+        }
+
+        fn distance(a: (i32, i32, i32), b: (i32, i32, i32)) -> i32 {
+            (a.0 - b.0).abs() + (a.1 - b.1).abs() + (a.2 - b.2).abs()
+        }
+
+        #[test]
+        fn small_mass_step_changes_color_smoothly() {
+            // 離散版のしきい値（10）をまたいでも色は少ししか変わらない
+            let below = rgb(9.9, 0.0, Vec2::ZERO);
+            let above = rgb(10.1, 0.0, Vec2::ZERO);
+            assert!(distance(below, above) <= 6);
+        }
+
+        #[test]
+        fn color_shifts_with_mass() {
+            let light = rgb(2.0, 0.0, Vec2::ZERO);
+            let heavy = rgb(40.0, 0.0, Vec2::ZERO);
+            assert!(distance(light, heavy) > 100);
+            // 重い語は黄（赤・緑が青より強い）
+            assert!(heavy.0 > heavy.2 && heavy.1 > heavy.2);
+        }
+
+        #[test]
+        fn fast_word_leans_cyan() {
+            let fast = rgb(8.0, 0.0, Vec2::new(20.0, 0.0));
+            assert!(fast.1 > fast.0 && fast.2 > fast.0);
+        }
+
+        #[test]
+        fn dusty_word_is_gray() {
+            let (r, g, b) = rgb(1.0, 99.0, Vec2::ZERO);
+            assert!((r - g).abs() <= 4 && (g - b).abs() <= 4);
+        }
+
+        #[test]
+        fn draw_uses_selected_mode() {
+            let mut text = [' '; TEXT_MAX_DRAW];
+            text[0] = 'C';
+            let mut snapshot = snapshot_with(30.0, 0.0, Vec2::ZERO);
+            snapshot.text = text;
+            snapshot.text_len = 1;
+            let camera = Camera::default();
+            let viewport = Viewport { width: 80, height: 24 };
+            let mut frame = FrameBuffer::new(80, 24);
+            let options = DrawOptions {
+                color_mode: WordColorMode::Continuous,
+                ..DrawOptions::default()
+            };
+
+            draw(&[snapshot], &[], None, &camera, viewport, &options, &mut frame);
+            assert!(matches!(frame.get(40, 12).color, ColorId::Rgb(..)));
+
+            draw(&[snapshot], &[], None, &camera, viewport, &DrawOptions::default(), &mut frame);
+            assert_eq!(frame.get(40, 12).color, ColorId::Yellow);
+        }
+    }
+
     mod draw_fn {
         use super::*;
 
@@ -391,7 +1630,7 @@ mod tests {
             let viewport = Viewport { width: 80, height: 24 };
             let mut frame = FrameBuffer::new(80, 24);
             
-            draw(&snapshot, &effects, None, &camera, viewport, &mut frame);
+            draw(&snapshot, &effects, None, &camera, viewport, &DrawOptions::default(), &mut frame);
             
             for y in 0..24 {
                 for x in 0..80 {
@@ -410,6 +1649,8 @@ mod tests {
                 id: 1,
                 text,
                 text_len: 2,
+                truncated: false,
+                display_width: text_display_width(&text[..2]),
                 pos: Vec2::ZERO,
                 radius: 1.0,
                 mass_visible: 10.0,
@@ -419,13 +1660,17 @@ mod tests {
                 trail: [Vec2::ZERO; TRAIL_LEN],
                 trail_len: 0,
                 trail_head: 0,
+                merge_pending: false,
+                merge_count: 0,
+                split_count: 0,
+                age: 0.0,
             }];
             let effects: Vec<EffectParticle> = Vec::new();
             let camera = Camera::default();
             let viewport = Viewport { width: 80, height: 24 };
             let mut frame = FrameBuffer::new(80, 24);
             
-            draw(&snapshot, &effects, None, &camera, viewport, &mut frame);
+            draw(&snapshot, &effects, None, &camera, viewport, &DrawOptions::default(), &mut frame);
             
             let center_x = 40;
             let center_y = 12;
@@ -433,6 +1678,82 @@ mod tests {
             assert_eq!(cell.ch, 'H');
         }
 
+        #[test]
+        fn truncated_word_ends_with_ellipsis() {
+            let mut text = [' '; TEXT_MAX_DRAW];
+            text[0] = 'a';
+            text[1] = 'b';
+            text[2] = '-';
+            let snapshot = vec![WordSnapshot {
+                id: 1,
+                text,
+                text_len: 3,
+                truncated: true,
+                display_width: text_display_width(&text[..3]),
+                pos: Vec2::ZERO,
+                radius: 1.0,
+                mass_visible: 10.0,
+                mass_total: 10.0,
+                mass_dust: 0.0,
+                vel: Vec2::ZERO,
+                trail: [Vec2::ZERO; TRAIL_LEN],
+                trail_len: 0,
+                trail_head: 0,
+                merge_pending: false,
+                merge_count: 0,
+                split_count: 0,
+                age: 0.0,
+            }];
+            let effects: Vec<EffectParticle> = Vec::new();
+            let camera = Camera::default();
+            let viewport = Viewport { width: 80, height: 24 };
+            let mut frame = FrameBuffer::new(80, 24);
+
+            draw(&snapshot, &effects, None, &camera, viewport, &DrawOptions::default(), &mut frame);
+
+            assert_eq!(frame.get(40, 12).ch, 'a');
+            assert_eq!(frame.get(41, 12).ch, 'b');
+            // 末尾の区切りダッシュは省略記号に置き換わる
+            assert_eq!(frame.get(42, 12).ch, ELLIPSIS);
+        }
+
+        #[test]
+        fn fullwidth_char_occupies_two_cells() {
+            let mut text = [' '; TEXT_MAX_DRAW];
+            text[0] = '語';
+            text[1] = 'x';
+            let snapshot = vec![WordSnapshot {
+                id: 1,
+                text,
+                text_len: 2,
+                truncated: false,
+                display_width: text_display_width(&text[..2]),
+                pos: Vec2::ZERO,
+                radius: 1.0,
+                mass_visible: 10.0,
+                mass_total: 10.0,
+                mass_dust: 0.0,
+                vel: Vec2::ZERO,
+                trail: [Vec2::ZERO; TRAIL_LEN],
+                trail_len: 0,
+                trail_head: 0,
+                merge_pending: false,
+                merge_count: 0,
+                split_count: 0,
+                age: 0.0,
+            }];
+            let effects: Vec<EffectParticle> = Vec::new();
+            let camera = Camera::default();
+            let viewport = Viewport { width: 80, height: 24 };
+            let mut frame = FrameBuffer::new(80, 24);
+
+            draw(&snapshot, &effects, None, &camera, viewport, &DrawOptions::default(), &mut frame);
+
+            assert_eq!(frame.get(40, 12).ch, '語');
+            assert_eq!(frame.get(41, 12).ch, WIDE_CONTINUATION);
+            assert_eq!(frame.get(42, 12).ch, 'x');
+        }
+
         #[test]
         fn focused_word_is_red() {
             let mut text = [' '; TEXT_MAX_DRAW];
@@ -441,6 +1762,8 @@ mod tests {
                 id: 1,
                 text,
                 text_len: 1,
+                truncated: false,
+                display_width: text_display_width(&text[..1]),
                 pos: Vec2::ZERO,
                 radius: 1.0,
                 mass_visible: 10.0,
@@ -450,18 +1773,117 @@ mod tests {
                 trail: [Vec2::ZERO; TRAIL_LEN],
                 trail_len: 0,
                 trail_head: 0,
+                merge_pending: false,
+                merge_count: 0,
+                split_count: 0,
+                age: 0.0,
             }];
             let effects: Vec<EffectParticle> = Vec::new();
             let camera = Camera::default();
             let viewport = Viewport { width: 80, height: 24 };
             let mut frame = FrameBuffer::new(80, 24);
             
-            draw(&snapshot, &effects, Some(1), &camera, viewport, &mut frame);
+            draw(&snapshot, &effects, Some(1), &camera, viewport, &DrawOptions::default(), &mut frame);
             
             let cell = frame.get(40, 12);
             assert_eq!(cell.color, ColorId::Red);
         }
 
+        #[test]
+        fn highlighted_word_uses_highlight_color() {
+            let mut text = [' '; TEXT_MAX_DRAW];
+            text[0] = 'X';
+            let snapshot = vec![WordSnapshot {
+                id: 7,
+                text,
+                text_len: 1,
+                truncated: false,
+                display_width: text_display_width(&text[..1]),
+                pos: Vec2::ZERO,
+                radius: 1.0,
+                mass_visible: 10.0,
+                mass_total: 10.0,
+                mass_dust: 0.0,
+                vel: Vec2::ZERO,
+                trail: [Vec2::ZERO; TRAIL_LEN],
+                trail_len: 0,
+                trail_head: 0,
+                merge_pending: false,
+                merge_count: 0,
+                split_count: 0,
+                age: 0.0,
+            }];
+            let effects: Vec<EffectParticle> = Vec::new();
+            let camera = Camera::default();
+            let viewport = Viewport { width: 80, height: 24 };
+            let mut frame = FrameBuffer::new(80, 24);
+            let options = DrawOptions {
+                highlight_ids: &[7],
+                ..DrawOptions::default()
+            };
+
+            draw(&snapshot, &effects, None, &camera, viewport, &options, &mut frame);
+            assert_eq!(frame.get(40, 12).color, ColorId::Highlight);
+
+            // フォーカス色はハイライトより優先される
+            draw(&snapshot, &effects, Some(7), &camera, viewport, &options, &mut frame);
+            assert_eq!(frame.get(40, 12).color, ColorId::Red);
+        }
+
+        #[test]
+        fn color_fn_overrides_default_but_not_focus() {
+            let word = |id: WordId, ch: char, x: f32| {
+                let mut text = [' '; TEXT_MAX_DRAW];
+                text[0] = ch;
+                WordSnapshot {
+                    id,
+                    text,
+                    text_len: 1,
+                    truncated: false,
+                    display_width: 1,
+                    pos: Vec2::new(x, 0.0),
+                    radius: 1.0,
+                    mass_visible: 10.0,
+                    mass_total: 10.0,
+                    mass_dust: 0.0,
+                    vel: Vec2::ZERO,
+                    trail: [Vec2::ZERO; TRAIL_LEN],
+                    trail_len: 0,
+                    trail_head: 0,
+                    merge_pending: false,
+                    merge_count: 0,
+                    split_count: 0,
+                    age: 0.0,
+                }
+            };
+            let snapshot = vec![word(1, 'A', 0.0), word(2, 'B', 10.0)];
+            let camera = Camera::default();
+            let viewport = Viewport { width: 80, height: 24 };
+            let color_fn = |w: &WordSnapshot| (w.text[0] == 'A').then_some(ColorId::Rgb(0, 200, 0));
+            let options = DrawOptions {
+                color_fn: Some(&color_fn),
+                ..DrawOptions::default()
+            };
+
+            let mut frame = FrameBuffer::new(80, 24);
+            draw(&snapshot, &[], None, &camera, viewport, &options, &mut frame);
+            assert_eq!(frame.get(40, 12).color, ColorId::Rgb(0, 200, 0));
+            // None を返した語は既定色のまま
+            assert_eq!(frame.get(50, 12).color, word_color(&snapshot[1]));
+
+            let mut frame = FrameBuffer::new(80, 24);
+            draw(&snapshot, &[], Some(1), &camera, viewport, &options, &mut frame);
+            assert_eq!(frame.get(40, 12).color, ColorId::Red);
+
+            let options = DrawOptions {
+                highlight_ids: &[1],
+                ..options
+            };
+            let mut frame = FrameBuffer::new(80, 24);
+            draw(&snapshot, &[], None, &camera, viewport, &options, &mut frame);
+            assert_eq!(frame.get(40, 12).color, ColorId::Highlight);
+        }
+
         #[test]
         fn effect_overrides_word() {
             let mut text = [' '; TEXT_MAX_DRAW];
@@ -470,6 +1892,8 @@ mod tests {
                 id: 1,
                 text,
                 text_len: 1,
+                truncated: false,
+                display_width: text_display_width(&text[..1]),
                 pos: Vec2::ZERO,
                 radius: 1.0,
                 mass_visible: 10.0,
@@ -479,6 +1903,10 @@ mod tests {
                 trail: [Vec2::ZERO; TRAIL_LEN],
                 trail_len: 0,
                 trail_head: 0,
+                merge_pending: false,
+                merge_count: 0,
+                split_count: 0,
+                age: 0.0,
             }];
             let effects = vec![EffectParticle {
                 pos: Vec2::ZERO,
@@ -486,15 +1914,1600 @@ mod tests {
                 ttl: 1.0,
                 glyph: '*',
                 color: ColorId::Yellow,
+                kind: EffectKind::Particle,
             }];
             let camera = Camera::default();
             let viewport = Viewport { width: 80, height: 24 };
             let mut frame = FrameBuffer::new(80, 24);
             
-            draw(&snapshot, &effects, None, &camera, viewport, &mut frame);
+            draw(&snapshot, &effects, None, &camera, viewport, &DrawOptions::default(), &mut frame);
             
             let cell = frame.get(40, 12);
             assert_eq!(cell.ch, '*');
         }
+
+        #[test]
+        fn aspect_halves_vertical_offset() {
+            let mut text = [' '; TEXT_MAX_DRAW];
+            text[0] = 'V';
+            let snapshot = vec![WordSnapshot {
+                id: 1,
+                text,
+                text_len: 1,
+                truncated: false,
+                display_width: text_display_width(&text[..1]),
+                pos: Vec2::new(0.0, 8.0),
+                radius: 1.0,
+                mass_visible: 10.0,
+                mass_total: 10.0,
+                mass_dust: 0.0,
+                vel: Vec2::ZERO,
+                trail: [Vec2::ZERO; TRAIL_LEN],
+                trail_len: 0,
+                trail_head: 0,
+                merge_pending: false,
+                merge_count: 0,
+                split_count: 0,
+                age: 0.0,
+            }];
+            let viewport = Viewport { width: 80, height: 24 };
+            let mut frame = FrameBuffer::new(80, 24);
+            let mut camera = Camera {
+                aspect: 2.0,
+                ..Camera::default()
+            };
+
+            draw(&snapshot, &[], None, &camera, viewport, &DrawOptions::default(), &mut frame);
+            assert_eq!(frame.get(40, 16).ch, 'V');
+
+            // 補正1.0なら従来どおり
+            camera.aspect = 1.0;
+            draw(&snapshot, &[], None, &camera, viewport, &DrawOptions::default(), &mut frame);
+            assert_eq!(frame.get(40, 20).ch, 'V');
+        }
+    }
+
+    mod draw_priority {
+        use super::*;
+
+        fn word_at(id: WordId, ch: char, mass: f32) -> WordSnapshot {
+            let mut text = [' '; TEXT_MAX_DRAW];
+            text[0] = ch;
+            WordSnapshot {
+                id,
+                text,
+                text_len: 1,
+                truncated: false,
+                display_width: text_display_width(&text[..1]),
+                pos: Vec2::ZERO,
+                radius: 1.0,
+                mass_visible: mass,
+                mass_total: mass,
+                mass_dust: 0.0,
+                vel: Vec2::ZERO,
+                trail: [Vec2::ZERO; TRAIL_LEN],
+                trail_len: 0,
+                trail_head: 0,
+                merge_pending: false,
+                merge_count: 0,
+                split_count: 0,
+                age: 0.0,
+            }
+        }
+
+        fn spark() -> EffectParticle {
+            EffectParticle {
+                pos: Vec2::ZERO,
+                vel: Vec2::ZERO,
+                ttl: 1.0,
+                glyph: '*',
+                color: ColorId::Spark,
+                kind: EffectKind::Particle,
+            }
+        }
+
+        #[test]
+        fn new_word_stays_in_front_until_it_ages() {
+            let old_heavy = WordSnapshot {
+                age: 60.0,
+                ..word_at(1, 'H', 20.0)
+            };
+            let camera = Camera::default();
+            let viewport = Viewport { width: 80, height: 24 };
+            let mut frame = FrameBuffer::new(80, 24);
+            let options = DrawOptions {
+                recency_weight: 30.0,
+                ..DrawOptions::default()
+            };
+            let mut front = |age: f32, options: &DrawOptions| {
+                let young = WordSnapshot { age, ..word_at(2, 'n', 1.0) };
+                draw(&[old_heavy, young], &[], None, &camera, viewport, options, &mut frame);
+                frame.get(40, 12).ch
+            };
+
+            assert_eq!(front(0.0, &options), 'n');
+            assert_eq!(front(config::RECENCY_PRIORITY_AGE * 0.3, &options), 'n');
+            assert_eq!(front(config::RECENCY_PRIORITY_AGE, &options), 'H');
+            // 重み 0 なら質量だけで決まる
+            assert_eq!(front(0.0, &DrawOptions::default()), 'H');
+        }
+
+        #[test]
+        fn focused_light_word_overwrites_heavier_word() {
+            let snapshot = vec![word_at(1, 'L', 1.0), word_at(2, 'H', 100.0)];
+            let camera = Camera::default();
+            let viewport = Viewport { width: 80, height: 24 };
+            let mut frame = FrameBuffer::new(80, 24);
+
+            draw(&snapshot, &[], Some(1), &camera, viewport, &DrawOptions::default(), &mut frame);
+
+            let cell = frame.get(40, 12);
+            assert_eq!(cell.ch, 'L');
+            assert_eq!(cell.color, ColorId::Red);
+        }
+
+        #[test]
+        fn focus_above_effects_hides_effect() {
+            let snapshot = vec![word_at(1, 'F', 1.0)];
+            let effects = vec![spark()];
+            let camera = Camera::default();
+            let viewport = Viewport { width: 80, height: 24 };
+            let mut frame = FrameBuffer::new(80, 24);
+            let options = DrawOptions {
+                focus_above_effects: true,
+                ..DrawOptions::default()
+            };
+
+            draw(&snapshot, &effects, Some(1), &camera, viewport, &options, &mut frame);
+
+            assert_eq!(frame.get(40, 12).ch, 'F');
+        }
+
+        #[test]
+        fn focus_below_effects_keeps_effect_on_top() {
+            let snapshot = vec![word_at(1, 'F', 1.0), word_at(2, 'H', 100.0)];
+            let effects = vec![spark()];
+            let camera = Camera::default();
+            let viewport = Viewport { width: 80, height: 24 };
+            let mut frame = FrameBuffer::new(80, 24);
+
+            draw(&snapshot, &effects, Some(1), &camera, viewport, &DrawOptions::default(), &mut frame);
+            assert_eq!(frame.get(40, 12).ch, '*');
+
+            // エフェクトが無ければ重い語より前に出る
+            draw(&snapshot, &[], Some(1), &camera, viewport, &DrawOptions::default(), &mut frame);
+            assert_eq!(frame.get(40, 12).ch, 'F');
+        }
+    }
+
+    mod deconflict {
+        use super::*;
+
+        fn word_at(id: WordId, ch: char, mass: f32) -> WordSnapshot {
+            let mut text = [' '; TEXT_MAX_DRAW];
+            text[0] = ch;
+            WordSnapshot {
+                id,
+                text,
+                text_len: 1,
+                truncated: false,
+                display_width: text_display_width(&text[..1]),
+                pos: Vec2::ZERO,
+                radius: 1.0,
+                mass_visible: mass,
+                mass_total: mass,
+                mass_dust: 0.0,
+                vel: Vec2::ZERO,
+                trail: [Vec2::ZERO; TRAIL_LEN],
+                trail_len: 0,
+                trail_head: 0,
+                merge_pending: false,
+                merge_count: 0,
+                split_count: 0,
+                age: 0.0,
+            }
+        }
+
+        fn render(snapshot: &[WordSnapshot], focus: Option<WordId>, shifts: usize) -> FrameBuffer {
+            let viewport = Viewport { width: 80, height: 24 };
+            let mut frame = FrameBuffer::new(80, 24);
+            let options = DrawOptions {
+                deconflict_shifts: shifts,
+                ..DrawOptions::default()
+            };
+            draw(snapshot, &[], focus, &Camera::default(), viewport, &options, &mut frame);
+            frame
+        }
+
+        #[test]
+        fn stacked_words_are_all_readable() {
+            let snapshot = vec![
+                word_at(1, 'L', 1.0),
+                word_at(2, 'H', 100.0),
+                word_at(3, 'M', 10.0),
+            ];
+            let frame = render(&snapshot, None, 3);
+            assert_eq!(frame.get(40, 12).ch, 'H');
+            assert_eq!(frame.get(40, 13).ch, 'M');
+            assert_eq!(frame.get(40, 14).ch, 'L');
+        }
+
+        #[test]
+        fn disabled_keeps_only_the_heaviest() {
+            let snapshot = vec![word_at(1, 'L', 1.0), word_at(2, 'H', 100.0)];
+            let frame = render(&snapshot, None, 0);
+            assert_eq!(frame.get(40, 12).ch, 'H');
+            assert_eq!(frame.get(40, 13).ch, ' ');
+        }
+
+        #[test]
+        fn focused_word_keeps_its_cell() {
+            let snapshot = vec![word_at(1, 'F', 1.0), word_at(2, 'H', 100.0)];
+            let frame = render(&snapshot, Some(1), 3);
+            assert_eq!(frame.get(40, 12).ch, 'F');
+            assert_eq!(frame.get(40, 13).ch, 'H');
+        }
+
+        #[test]
+        fn shifts_are_capped() {
+            let snapshot: Vec<WordSnapshot> = (1..=5)
+                .map(|id| word_at(id, char::from(b'0' + id as u8), id as f32))
+                .collect();
+            let frame = render(&snapshot, None, 2);
+            assert_eq!(frame.get(40, 12).ch, '5');
+            assert_eq!(frame.get(40, 13).ch, '4');
+            assert_eq!(frame.get(40, 14).ch, '3');
+            assert_eq!(frame.get(40, 15).ch, ' ');
+        }
+    }
+
+    mod prediction {
+        use super::*;
+
+        #[test]
+        fn draws_points_beneath_words() {
+            let mut text = [' '; TEXT_MAX_DRAW];
+            text[0] = 'W';
+            let word = WordSnapshot {
+                id: 1,
+                text,
+                text_len: 1,
+                truncated: false,
+                display_width: text_display_width(&text[..1]),
+                pos: Vec2::ZERO,
+                radius: 1.0,
+                mass_visible: 1.0,
+                mass_total: 1.0,
+                mass_dust: 0.0,
+                vel: Vec2::ZERO,
+                trail: [Vec2::ZERO; TRAIL_LEN],
+                trail_len: 0,
+                trail_head: 0,
+                merge_pending: false,
+                merge_count: 0,
+                split_count: 0,
+                age: 0.0,
+            };
+            let points = [Vec2::ZERO, Vec2::new(5.0, 0.0), Vec2::new(500.0, 0.0)];
+            let viewport = Viewport { width: 80, height: 24 };
+            let mut frame = FrameBuffer::new(80, 24);
+            let options = DrawOptions {
+                prediction: &points,
+                ..DrawOptions::default()
+            };
+            draw(&[word], &[], None, &Camera::default(), viewport, &options, &mut frame);
+
+            assert_eq!(frame.get(40, 12).ch, 'W');
+            let cell = frame.get(45, 12);
+            assert_eq!(cell.ch, PREDICTION_GLYPH);
+            assert_eq!(cell.color, ColorId::Prediction);
+        }
+    }
+
+    mod heatmap {
+        use super::*;
+        use crate::types::ActivityCell;
+
+        #[test]
+        fn hot_cells_are_shaded_by_category_beneath_words() {
+            let mut map = ActivityMap { cell_size: 10.0, ..ActivityMap::default() };
+            map.cells.insert((0, 0), ActivityCell { merges: 4.0, splits: 0.0 });
+            map.cells.insert((-1, 0), ActivityCell { merges: 0.0, splits: 1.0 });
+            map.cells.insert((0, -1), ActivityCell { merges: 0.1, splits: 0.0 });
+            let mut text = [' '; TEXT_MAX_DRAW];
+            text[0] = 'W';
+            let word = WordSnapshot {
+                id: 1,
+                text,
+                text_len: 1,
+                truncated: false,
+                display_width: text_display_width(&text[..1]),
+                pos: Vec2::new(5.0, 5.0),
+                radius: 1.0,
+                mass_visible: 1.0,
+                mass_total: 1.0,
+                mass_dust: 0.0,
+                vel: Vec2::ZERO,
+                trail: [Vec2::new(5.0, 5.0); TRAIL_LEN],
+                trail_len: 0,
+                trail_head: 0,
+                merge_pending: false,
+                merge_count: 0,
+                split_count: 0,
+                age: 0.0,
+            };
+            let viewport = Viewport { width: 80, height: 24 };
+            let mut frame = FrameBuffer::new(80, 24);
+            let camera = Camera { aspect: 1.0, ..Camera::default() };
+            let options = DrawOptions { heatmap: Some(&map), ..DrawOptions::default() };
+            draw(&[word], &[], None, &camera, viewport, &options, &mut frame);
+
+            let hot = frame.get(42, 13);
+            assert_eq!((hot.ch, hot.color), ('█', ColorId::Yellow));
+            let split = frame.get(35, 13);
+            assert_eq!((split.ch, split.color), ('░', ColorId::Cyan));
+            assert_eq!(frame.get(45, 17).ch, 'W');
+            // 最大の 5% 未満は塗らない
+            assert_eq!(frame.get(42, 8).ch, ' ');
+            assert_eq!(frame.get(60, 3).ch, ' ');
+        }
+    }
+
+    mod combining_marks {
+        use super::*;
+
+        fn word(text: &str) -> WordSnapshot {
+            let (mut chars, mut len) = ([' '; TEXT_MAX_DRAW], 0);
+            for ch in text.chars() {
+                chars[len] = ch;
+                len += 1;
+            }
+            WordSnapshot {
+                id: 1,
+                text: chars,
+                text_len: len,
+                truncated: false,
+                display_width: text_display_width(&chars[..len]),
+                pos: Vec2::ZERO,
+                radius: 1.0,
+                mass_visible: 1.0,
+                mass_total: 1.0,
+                mass_dust: 0.0,
+                vel: Vec2::ZERO,
+                trail: [Vec2::ZERO; TRAIL_LEN],
+                trail_len: 0,
+                trail_head: 0,
+                merge_pending: false,
+                merge_count: 0,
+                split_count: 0,
+                age: 0.0,
+            }
+        }
+
+        fn draw_text(text: &str, options: &DrawOptions) -> FrameBuffer {
+            let viewport = Viewport { width: 20, height: 6 };
+            let mut frame = FrameBuffer::new(20, 6);
+            draw(&[word(text)], &[], None, &Camera::default(), viewport, options, &mut frame);
+            frame
+        }
+
+        #[test]
+        fn marks_ride_on_the_previous_cell() {
+            let frame = draw_text("か\u{3099}e\u{301}x", &DrawOptions::default());
+            assert_eq!(frame.to_text().lines().nth(3), Some("          か\u{3099}e\u{301}x"));
+            assert_eq!(frame.get(12, 3).ch, 'e');
+            assert_eq!(frame.get(12, 3).visible_mark(), Some('\u{301}'));
+            assert_eq!(frame.get(13, 3).ch, 'x');
+        }
+
+        #[test]
+        fn overwritten_cell_drops_its_mark() {
+            let mut frame = draw_text("e\u{301}", &DrawOptions::default());
+            frame.set(10, 3, 'z', 1.0e9, ColorId::White);
+            assert_eq!(frame.get(10, 3).visible_mark(), None);
+        }
+
+        #[test]
+        fn vertical_text_does_not_spend_a_row_on_marks() {
+            let options = DrawOptions { vertical_text: true, ..DrawOptions::default() };
+            let frame = draw_text("e\u{301}x", &options);
+            assert_eq!(frame.get(10, 3).visible_mark(), Some('\u{301}'));
+            assert_eq!(frame.get(10, 4).ch, 'x');
+        }
+    }
+
+    mod springs {
+        use super::*;
+
+        #[test]
+        fn draws_segment_between_words() {
+            let points = [(Vec2::new(-10.0, 0.0), Vec2::new(10.0, 0.0))];
+            let viewport = Viewport { width: 80, height: 24 };
+            let mut frame = FrameBuffer::new(80, 24);
+            let options = DrawOptions {
+                springs: &points,
+                ..DrawOptions::default()
+            };
+            draw(&[], &[], None, &Camera::default(), viewport, &options, &mut frame);
+
+            for x in 30..=50 {
+                assert_eq!(frame.get(x, 12).ch, config::SPRING_GLYPH, "x={x}");
+            }
+            assert_eq!(frame.get(29, 12).ch, ' ');
+            assert_eq!(frame.get(40, 11).ch, ' ');
+        }
+    }
+
+    mod grid {
+        use super::*;
+
+        fn render(cell_size: f32, words: &[Vec2]) -> FrameBuffer {
+            let snapshot: Vec<WordSnapshot> = words
+                .iter()
+                .enumerate()
+                .map(|(i, &pos)| {
+                    let mut text = [' '; TEXT_MAX_DRAW];
+                    text[0] = 'W';
+                    WordSnapshot {
+                        id: i as WordId + 1,
+                        text,
+                        text_len: 1,
+                        truncated: false,
+                        display_width: text_display_width(&text[..1]),
+                        pos,
+                        radius: 1.0,
+                        mass_visible: 1.0,
+                        mass_total: 1.0,
+                        mass_dust: 0.0,
+                        vel: Vec2::ZERO,
+                        trail: [Vec2::ZERO; TRAIL_LEN],
+                        trail_len: 0,
+                        trail_head: 0,
+                        merge_pending: false,
+                        merge_count: 0,
+                        split_count: 0,
+                        age: 0.0,
+                    }
+                })
+                .collect();
+            let viewport = Viewport { width: 40, height: 12 };
+            let mut frame = FrameBuffer::new(40, 12);
+            let options = DrawOptions {
+                grid_cell_size: cell_size,
+                grid_counts: true,
+                ..DrawOptions::default()
+            };
+            draw(&snapshot, &[], None, &Camera::default(), viewport, &options, &mut frame);
+            frame
+        }
+
+        #[test]
+        fn draws_cell_boundaries_under_words() {
+            let frame = render(16.0, &[Vec2::new(0.0, 4.0)]);
+            // x = -16, 0, 16 は列 4, 20, 36。y = 0 は行 6
+            for x in [4, 20, 36] {
+                assert_eq!(frame.get(x, 0).ch, '┊');
+            }
+            assert_eq!(frame.get(10, 6).ch, '┈');
+            assert_eq!(frame.get(20, 6).ch, '┼');
+            assert_eq!(frame.get(20, 0).priority, GRID_PRIORITY);
+            // 語は (20, 8) に描かれ、セル (0, 0) の語数は左上 (21, 7) に出る
+            assert_eq!(frame.get(20, 8).ch, 'W');
+            assert_eq!(frame.get(21, 7).ch, '1');
+        }
+
+        #[test]
+        fn boundaries_follow_the_cell_size() {
+            let frame = render(8.0, &[]);
+            for x in [4, 12, 20, 28, 36] {
+                assert_eq!(frame.get(x, 0).ch, '┊');
+            }
+            assert_eq!(frame.get(10, 2).ch, '┈');
+            assert_eq!(frame.get(10, 10).ch, '┈');
+        }
+
+        #[test]
+        fn counts_words_per_cell_and_can_be_disabled() {
+            let frame = render(16.0, &[Vec2::new(3.0, 6.0), Vec2::new(2.0, 14.0)]);
+            assert_eq!(frame.get(21, 7).ch, '2');
+            let frame = render(0.0, &[]);
+            assert_eq!(frame.get(20, 6).ch, ' ');
+        }
+    }
+
+    mod layer_order {
+        use super::*;
+
+        fn render(layer_order: LayerOrder) -> FrameBuffer {
+            let mut text = [' '; TEXT_MAX_DRAW];
+            text[0] = 'W';
+            let word = WordSnapshot {
+                id: 1,
+                text,
+                text_len: 1,
+                truncated: false,
+                display_width: text_display_width(&text[..1]),
+                pos: Vec2::ZERO,
+                radius: 1.0,
+                mass_visible: 1.0,
+                mass_total: 1.0,
+                mass_dust: 0.0,
+                vel: Vec2::ZERO,
+                trail: [Vec2::ZERO; TRAIL_LEN],
+                trail_len: 0,
+                trail_head: 0,
+                merge_pending: false,
+                merge_count: 0,
+                split_count: 0,
+                age: 0.0,
+            };
+            let effect = |x: f32| EffectParticle {
+                pos: Vec2::new(x, 0.0),
+                vel: Vec2::ZERO,
+                ttl: 1.0,
+                glyph: '*',
+                color: ColorId::Spark,
+                kind: EffectKind::Particle,
+            };
+            let viewport = Viewport { width: 40, height: 12 };
+            let mut frame = FrameBuffer::new(40, 12);
+            let options = DrawOptions {
+                layer_order,
+                ..DrawOptions::default()
+            };
+            let effects = [effect(0.0), effect(5.0)];
+            draw(&[word], &effects, None, &Camera::default(), viewport, &options, &mut frame);
+            frame
+        }
+
+        #[test]
+        fn effects_cover_words_by_default() {
+            assert_eq!(LayerOrder::default(), LayerOrder::EffectsOnTop);
+            let frame = render(LayerOrder::default());
+            assert_eq!(frame.get(20, 6).ch, '*');
+            assert_eq!(frame.get(25, 6).ch, '*');
+        }
+
+        #[test]
+        fn words_on_top_keeps_words_and_fills_empty_cells() {
+            let frame = render(LayerOrder::WordsOnTop);
+            assert_eq!(frame.get(20, 6).ch, 'W');
+            assert_eq!(frame.get(25, 6).ch, '*');
+        }
+    }
+
+    mod focus_frame {
+        use super::*;
+
+        fn word(id: WordId, text_str: &str, pos: Vec2, mass: f32) -> WordSnapshot {
+            let mut text = [' '; TEXT_MAX_DRAW];
+            for (slot, ch) in text.iter_mut().zip(text_str.chars()) {
+                *slot = ch;
+            }
+            WordSnapshot {
+                id,
+                text,
+                text_len: text_str.chars().count(),
+                truncated: false,
+                display_width: text_display_width(&text[..text_str.chars().count()]),
+                pos,
+                radius: 1.0,
+                mass_visible: mass,
+                mass_total: mass,
+                mass_dust: 0.0,
+                vel: Vec2::ZERO,
+                trail: [Vec2::ZERO; TRAIL_LEN],
+                trail_len: 0,
+                trail_head: 0,
+                merge_pending: false,
+                merge_count: 0,
+                split_count: 0,
+                age: 0.0,
+            }
+        }
+
+        fn render(snapshot: &[WordSnapshot], focus: Option<WordId>) -> FrameBuffer {
+            let viewport = Viewport { width: 40, height: 12 };
+            let mut frame = FrameBuffer::new(40, 12);
+            let options = DrawOptions {
+                focus_frame: true,
+                focus_above_effects: true,
+                ..DrawOptions::default()
+            };
+            draw(snapshot, &[], focus, &Camera::default(), viewport, &options, &mut frame);
+            frame
+        }
+
+        fn row(frame: &FrameBuffer, y: u16, from: u16, to: u16) -> String {
+            (from..to).map(|x| frame.get(x, y).ch).collect()
+        }
+
+        #[test]
+        fn surrounds_the_focused_word() {
+            let frame = render(&[word(1, "abc", Vec2::ZERO, 1.0)], Some(1));
+            assert_eq!(row(&frame, 5, 19, 24), "┌───┐");
+            assert_eq!(row(&frame, 6, 19, 24), "│abc│");
+            assert_eq!(row(&frame, 7, 19, 24), "└───┘");
+        }
+
+        #[test]
+        fn matches_wide_character_width() {
+            let frame = render(&[word(1, "語x", Vec2::ZERO, 1.0)], Some(1));
+            assert_eq!(row(&frame, 5, 19, 24), "┌───┐");
+            assert_eq!(frame.get(19, 6).ch, '│');
+            assert_eq!(frame.get(20, 6).ch, '語');
+            assert_eq!(frame.get(22, 6).ch, 'x');
+            assert_eq!(frame.get(23, 6).ch, '│');
+        }
+
+        #[test]
+        fn clips_at_the_viewport_edge() {
+            // 左上隅の語は枠の左と上が画面外
+            let frame = render(&[word(1, "ab", Vec2::new(-20.0, -12.0), 1.0)], Some(1));
+            assert_eq!(row(&frame, 0, 0, 3), "ab│");
+            assert_eq!(row(&frame, 1, 0, 3), "──┘");
+        }
+
+        #[test]
+        fn keeps_overlapping_words_and_follows_focus() {
+            let snapshot = [
+                word(1, "abc", Vec2::ZERO, 1.0),
+                word(2, "z", Vec2::new(-1.0, 0.0), 0.3),
+            ];
+            let frame = render(&snapshot, Some(1));
+            assert_eq!(frame.get(19, 6).ch, 'z');
+
+            let frame = render(&snapshot, Some(2));
+            assert_eq!(frame.get(18, 6).ch, '│');
+            assert_eq!(frame.get(19, 6).ch, 'z');
+            assert_eq!(frame.get(20, 5).ch, '┐');
+            assert_eq!(row(&frame, 6, 20, 23), "abc");
+
+            let frame = render(&snapshot, None);
+            assert_eq!(frame.get(18, 6).ch, ' ');
+        }
+    }
+
+    mod gravity_overlay {
+        use super::*;
+
+        fn focus_word() -> WordSnapshot {
+            let mut text = [' '; TEXT_MAX_DRAW];
+            text[0] = 'F';
+            WordSnapshot {
+                id: 1,
+                text,
+                text_len: 1,
+                truncated: false,
+                display_width: text_display_width(&text[..1]),
+                pos: Vec2::ZERO,
+                radius: 1.0,
+                mass_visible: 1.0,
+                mass_total: 1.0,
+                mass_dust: 0.0,
+                vel: Vec2::ZERO,
+                trail: [Vec2::ZERO; TRAIL_LEN],
+                trail_len: 0,
+                trail_head: 0,
+                merge_pending: false,
+                merge_count: 0,
+                split_count: 0,
+                age: 0.0,
+            }
+        }
+
+        fn render(focus: Option<WordId>, enabled: bool) -> FrameBuffer {
+            let camera = Camera {
+                zoom: 0.5,
+                ..Camera::default()
+            };
+            let viewport = Viewport { width: 200, height: 60 };
+            let mut frame = FrameBuffer::new(200, 60);
+            let options = DrawOptions {
+                gravity_overlay: enabled,
+                ..DrawOptions::default()
+            };
+            draw(&[focus_word()], &[], focus, &camera, viewport, &options, &mut frame);
+            frame
+        }
+
+        fn overlay_cells(frame: &FrameBuffer) -> usize {
+            (0..frame.height())
+                .flat_map(|y| (0..frame.width()).map(move |x| (x, y)))
+                .filter(|&(x, y)| frame.get(x, y).ch == OVERLAY_GLYPH)
+                .count()
+        }
+
+        #[test]
+        fn circles_follow_zoom_and_aspect() {
+            let frame = render(Some(1), true);
+            // cutoff 96 * zoom 0.5 = 48 セル、縦はさらに aspect 2 で半分
+            let cutoff = frame.get(100 + 48, 30);
+            assert_eq!((cutoff.ch, cutoff.color), (OVERLAY_GLYPH, ColorId::Gray));
+            assert_eq!(frame.get(100, 30 + 24).color, ColorId::Gray);
+            let fade = (config::GRAVITY_CUTOFF * config::GRAVITY_CUTOFF_FADE_START * 0.5).round();
+            let fade_cell = frame.get(100 + fade as u16, 30);
+            assert_eq!((fade_cell.ch, fade_cell.color), (OVERLAY_GLYPH, ColorId::Blue));
+            assert_eq!(frame.get(100, 30).ch, 'F');
+        }
+
+        #[test]
+        fn needs_focus_and_toggle() {
+            assert_eq!(overlay_cells(&render(None, true)), 0);
+            assert_eq!(overlay_cells(&render(Some(1), false)), 0);
+            assert!(overlay_cells(&render(Some(1), true)) > 0);
+        }
+    }
+
+    mod wrap_lines_fn {
+        use super::*;
+
+        fn chars(text: &str) -> Vec<char> {
+            text.chars().collect()
+        }
+
+        fn wrapped(text: &str, max_width: usize) -> Vec<String> {
+            let text = chars(text);
+            wrap_lines(&text, max_width)
+                .into_iter()
+                .map(|(start, end)| text[start..end].iter().collect())
+                .collect()
+        }
+
+        #[test]
+        fn short_text_stays_on_one_line() {
+            assert_eq!(wrapped("ab-cd", 12), ["ab-cd"]);
+        }
+
+        #[test]
+        fn breaks_at_component_boundaries() {
+            assert_eq!(wrapped("abcd-efgh-ij", 9), ["abcd-efgh", "ij"]);
+            assert_eq!(wrapped("abcd-efgh-ij", 5), ["abcd", "efgh", "ij"]);
+        }
+
+        #[test]
+        fn long_component_is_split_by_width() {
+            assert_eq!(wrapped("abcdefgh", 3), ["abc", "def", "gh"]);
+        }
+
+        #[test]
+        fn fullwidth_counts_two_cells() {
+            assert_eq!(wrapped("卒論-研究-進学", 9), ["卒論-研究", "進学"]);
+            assert_eq!(wrapped("卒論-研究-進学", 8), ["卒論", "研究", "進学"]);
+            assert_eq!(wrapped("卒論研究", 4), ["卒論", "研究"]);
+        }
+
+        #[test]
+        fn empty_text_has_single_empty_line() {
+            assert_eq!(wrapped("", 4), [""]);
+        }
+    }
+
+    mod word_wrap {
+        use super::*;
+
+        #[test]
+        fn wrapped_word_is_centered_vertically() {
+            let mut text = [' '; TEXT_MAX_DRAW];
+            let source: Vec<char> = "abcdefgh-ijklmnop-qrst".chars().collect();
+            text[..source.len()].copy_from_slice(&source);
+            let snapshot = vec![WordSnapshot {
+                id: 1,
+                text,
+                text_len: source.len(),
+                truncated: false,
+                display_width: text_display_width(&text[..source.len()]),
+                pos: Vec2::ZERO,
+                radius: 1.0,
+                mass_visible: 10.0,
+                mass_total: 10.0,
+                mass_dust: 0.0,
+                vel: Vec2::ZERO,
+                trail: [Vec2::ZERO; TRAIL_LEN],
+                trail_len: 0,
+                trail_head: 0,
+                merge_pending: false,
+                merge_count: 0,
+                split_count: 0,
+                age: 0.0,
+            }];
+            let camera = Camera::default();
+            let viewport = Viewport { width: 80, height: 24 };
+            let mut frame = FrameBuffer::new(80, 24);
+            let options = DrawOptions {
+                word_wrap: true,
+                ..DrawOptions::default()
+            };
+
+            draw(&snapshot, &[], Some(1), &camera, viewport, &options, &mut frame);
+
+            // 3行に折れて中心行が語の位置に来る
+            assert_eq!(frame.get(40, 11).ch, 'a');
+            assert_eq!(frame.get(40, 12).ch, 'i');
+            assert_eq!(frame.get(40, 13).ch, 'q');
+            assert_eq!(frame.get(40, 12).color, ColorId::Red);
+            assert_eq!(frame.get(40 + config::WORD_WRAP_WIDTH as u16, 11).ch, ' ');
+
+            draw(&snapshot, &[], None, &camera, viewport, &DrawOptions::default(), &mut frame);
+            assert_eq!(frame.get(40, 11).ch, ' ');
+            assert_eq!(frame.get(48, 12).ch, '-');
+        }
+    }
+
+    mod edge_clipping {
+        use super::*;
+
+        fn word(text: &str, x: f32, truncated: bool) -> WordSnapshot {
+            let mut chars = [' '; TEXT_MAX_DRAW];
+            for (slot, ch) in chars.iter_mut().zip(text.chars()) {
+                *slot = ch;
+            }
+            let len = text.chars().count();
+            WordSnapshot {
+                id: 1,
+                text: chars,
+                text_len: len,
+                truncated,
+                display_width: text_display_width(&chars[..len]),
+                pos: Vec2::new(x, 0.0),
+                radius: 1.0,
+                mass_visible: 10.0,
+                mass_total: 10.0,
+                mass_dust: 0.0,
+                vel: Vec2::ZERO,
+                trail: [Vec2::ZERO; TRAIL_LEN],
+                trail_len: 0,
+                trail_head: 0,
+                merge_pending: false,
+                merge_count: 0,
+                split_count: 0,
+                age: 0.0,
+            }
+        }
+
+        fn render(word: WordSnapshot, focus: Option<WordId>) -> FrameBuffer {
+            let viewport = Viewport { width: 80, height: 24 };
+            let mut frame = FrameBuffer::new(80, 24);
+            let options = DrawOptions {
+                focus_frame: true,
+                ..DrawOptions::default()
+            };
+            let camera = Camera::default();
+            draw(&[word], &[], focus, &camera, viewport, &options, &mut frame);
+            frame
+        }
+
+        #[test]
+        fn word_hanging_off_the_left_edge_keeps_its_visible_tail() {
+            // "卒論ab" は6セル。左端の2セルだけ画面外
+            let frame = render(word("卒論ab", -42.0, false), None);
+            assert_eq!(frame.get(0, 12).ch, '論');
+            assert_eq!(frame.get(2, 12).ch, 'a');
+            assert_eq!(frame.get(3, 12).ch, 'b');
+
+            let frame = render(word("卒論ab", -45.0, false), None);
+            assert_eq!(frame.get(0, 12).ch, 'b');
+
+            let frame = render(word("卒論ab", -45.0, true), None);
+            assert_eq!(frame.get(1, 12).ch, ELLIPSIS);
+        }
+
+        #[test]
+        fn word_entirely_off_screen_draws_nothing() {
+            for x in [-46.0, -60.0, 40.0, 100.0] {
+                let frame = render(word("卒論ab", x, false), None);
+                assert!(frame.to_text().chars().all(|ch| ch == ' ' || ch == '\n'), "{x}");
+            }
+        }
+
+        #[test]
+        fn focus_frame_spans_display_width() {
+            let vertical = FOCUS_FRAME_GLYPHS[5];
+            let frame = render(word("卒論ab", 0.0, false), Some(1));
+            assert_eq!(frame.get(39, 12).ch, vertical);
+            assert_eq!(frame.get(46, 12).ch, vertical);
+
+            // 末尾の '-' は省略記号に置き換わる
+            let frame = render(word("ab-", 0.0, true), Some(1));
+            assert_eq!(frame.get(42, 12).ch, ELLIPSIS);
+            assert_eq!(frame.get(43, 12).ch, vertical);
+        }
+    }
+
+    mod merge_animation {
+        use super::*;
+
+        fn word(id: WordId, text: &str, x: f32) -> WordSnapshot {
+            let mut chars = [' '; TEXT_MAX_DRAW];
+            for (slot, ch) in chars.iter_mut().zip(text.chars()) {
+                *slot = ch;
+            }
+            let len = text.chars().count();
+            WordSnapshot {
+                id,
+                text: chars,
+                text_len: len,
+                truncated: false,
+                display_width: text_display_width(&chars[..len]),
+                pos: Vec2::new(x, 0.0),
+                radius: 1.0,
+                mass_visible: 10.0,
+                mass_total: 10.0,
+                mass_dust: 0.0,
+                vel: Vec2::ZERO,
+                trail: [Vec2::ZERO; TRAIL_LEN],
+                trail_len: 0,
+                trail_head: 0,
+                merge_pending: false,
+                merge_count: 0,
+                split_count: 0,
+                age: 0.0,
+            }
+        }
+
+        fn record() -> MergeRecord {
+            MergeRecord {
+                merged: 3,
+                pos: Vec2::ZERO,
+                sources: vec![word(1, "ab", -20.0), word(2, "cd", 20.0)],
+            }
+        }
+
+        #[test]
+        fn ghosts_ease_from_sources_to_target() {
+            let record = record();
+            let (mut ghosts, mut streaks) = (Vec::new(), Vec::new());
+            merge_ghosts(&record, Vec2::ZERO, 0.0, &mut ghosts, &mut streaks);
+            assert_eq!(ghosts[0].pos, Vec2::new(-20.0, 0.0));
+            assert_eq!(ghosts[1].pos, Vec2::new(20.0, 0.0));
+            assert_eq!(streaks.len(), 2);
+
+            ghosts.clear();
+            merge_ghosts(&record, Vec2::ZERO, 0.5, &mut ghosts, &mut streaks);
+            // 減速しながら近づくので、半分の時間で半分より先にいる
+            assert!(ghosts[0].pos.x > -10.0 && ghosts[0].pos.x < 0.0);
+
+            ghosts.clear();
+            merge_ghosts(&record, Vec2::new(4.0, 0.0), 1.0, &mut ghosts, &mut streaks);
+            assert!(ghosts.iter().all(|g| g.pos == Vec2::new(4.0, 0.0)));
+        }
+
+        #[test]
+        fn hidden_merged_word_gives_way_to_ghosts() {
+            let record = record();
+            let (mut ghosts, mut streaks) = (Vec::new(), Vec::new());
+            merge_ghosts(&record, Vec2::ZERO, 0.0, &mut ghosts, &mut streaks);
+            let viewport = Viewport { width: 80, height: 24 };
+            let mut frame = FrameBuffer::new(80, 24);
+            let options = DrawOptions {
+                merge_ghosts: &ghosts,
+                merge_hidden: &[3],
+                ..DrawOptions::default()
+            };
+            let merged = word(3, "abcd", 0.0);
+            draw(&[merged], &[], None, &Camera::default(), viewport, &options, &mut frame);
+            let text = frame.to_text();
+            assert!(!text.contains("abcd"));
+            assert!(text.contains("ab") && text.contains("cd"));
+        }
+    }
+
+    mod vertical_text {
+        use super::*;
+
+        fn word(id: WordId, text: &str, pos: Vec2, mass: f32) -> WordSnapshot {
+            let mut chars = [' '; TEXT_MAX_DRAW];
+            for (slot, ch) in chars.iter_mut().zip(text.chars()) {
+                *slot = ch;
+            }
+            WordSnapshot {
+                id,
+                text: chars,
+                text_len: text.chars().count(),
+                truncated: false,
+                display_width: text_display_width(&chars[..text.chars().count()]),
+                pos,
+                radius: 1.0,
+                mass_visible: mass,
+                mass_total: mass,
+                mass_dust: 0.0,
+                vel: Vec2::ZERO,
+                trail: [Vec2::ZERO; TRAIL_LEN],
+                trail_len: 0,
+                trail_head: 0,
+                merge_pending: false,
+                merge_count: 0,
+                split_count: 0,
+                age: 0.0,
+            }
+        }
+
+        fn render(words: &[WordSnapshot], focus: Option<WordId>, height: u16) -> FrameBuffer {
+            let viewport = Viewport { width: 20, height };
+            let mut frame = FrameBuffer::new(20, height);
+            let options = DrawOptions {
+                vertical_text: true,
+                focus_frame: true,
+                ..DrawOptions::default()
+            };
+            draw(words, &[], focus, &Camera::default(), viewport, &options, &mut frame);
+            frame
+        }
+
+        #[test]
+        fn stacks_characters_downward_from_the_anchor() {
+            let frame = render(&[word(1, "天体観測", Vec2::ZERO, 5.0)], None, 12);
+            for (row, ch) in "天体観測".chars().enumerate() {
+                let cell = frame.get(10, 6 + row as u16);
+                assert_eq!(cell.ch, ch);
+                assert_eq!(cell.priority, 5.0);
+                assert_eq!(frame.get(11, 6 + row as u16).ch, WIDE_CONTINUATION);
+            }
+            assert_eq!(frame.get(12, 6).ch, ' ');
+            assert_eq!(frame.to_text().lines().nth(7), Some("          体"));
+        }
+
+        #[test]
+        fn clips_at_the_bottom_and_marks_truncation() {
+            let mut long = word(1, "abcdefgh", Vec2::ZERO, 5.0);
+            long.truncated = true;
+            let frame = render(&[long], None, 12);
+            assert_eq!(frame.get(10, 11).ch, 'f');
+
+            let short = {
+                let mut w = word(1, "ab", Vec2::ZERO, 5.0);
+                w.truncated = true;
+                w
+            };
+            let frame = render(&[short], None, 12);
+            assert_eq!(frame.get(10, 8).ch, ELLIPSIS);
+        }
+
+        #[test]
+        fn heavier_and_focused_words_win_overlaps() {
+            let light = word(1, "LL", Vec2::ZERO, 1.0);
+            let heavy = word(2, "HH", Vec2::new(0.0, 2.0), 9.0);
+            let frame = render(&[light, heavy], None, 12);
+            // light は行 6,7、heavy は行 7,8 に積まれる
+            assert_eq!(frame.get(10, 6).ch, 'L');
+            assert_eq!(frame.get(10, 7).ch, 'H');
+
+            let frame = render(&[light, heavy], Some(1), 12);
+            assert_eq!(frame.get(10, 7).ch, 'L');
+            assert_eq!(frame.get(10, 7).color, ColorId::Red);
+        }
+
+        #[test]
+        fn focus_frame_surrounds_the_column() {
+            let frame = render(&[word(1, "天体", Vec2::ZERO, 5.0)], Some(1), 12);
+            assert_eq!(frame.get(9, 5).ch, '┌');
+            assert_eq!(frame.get(12, 5).ch, '┐');
+            assert_eq!(frame.get(9, 8).ch, '└');
+            assert_eq!(frame.get(12, 8).ch, '┘');
+            assert_eq!(frame.get(9, 7).ch, '│');
+        }
+    }
+
+    mod density_shading {
+        use super::*;
+
+        fn blob(id: WordId, ch: char, radius: f32, mass: f32) -> WordSnapshot {
+            let mut text = [' '; TEXT_MAX_DRAW];
+            text[0] = ch;
+            WordSnapshot {
+                id,
+                text,
+                text_len: 1,
+                truncated: false,
+                display_width: text_display_width(&text[..1]),
+                pos: Vec2::ZERO,
+                radius,
+                mass_visible: mass,
+                mass_total: mass,
+                mass_dust: 0.0,
+                vel: Vec2::ZERO,
+                trail: [Vec2::ZERO; TRAIL_LEN],
+                trail_len: 0,
+                trail_head: 0,
+                merge_pending: false,
+                merge_count: 0,
+                split_count: 0,
+                age: 0.0,
+            }
+        }
+
+        fn shaded(density_shading: bool) -> DrawOptions<'static> {
+            DrawOptions {
+                density_shading,
+                ..DrawOptions::default()
+            }
+        }
+
+        #[test]
+        fn overlapping_words_shade_background_and_keep_text() {
+            let snapshot = vec![blob(1, 'A', 3.0, 12.0), blob(2, 'B', 3.0, 8.0)];
+            let camera = Camera::default();
+            let viewport = Viewport { width: 80, height: 24 };
+            let mut frame = FrameBuffer::new(80, 24);
+
+            draw(&snapshot, &[], None, &camera, viewport, &shaded(true), &mut frame);
+
+            // テキストは前面に残る
+            assert_eq!(frame.get(40, 12).ch, 'A');
+            // 周囲は累積質量に応じた濃淡グリフ
+            let around = frame.get(38, 12);
+            assert!(DENSITY_GLYPHS.contains(&around.ch));
+            assert_eq!(around.color, ColorId::Shade);
+            assert_eq!(frame.density(38, 12), 20.0);
+            // 半径の外は空白のまま
+            assert_eq!(frame.get(50, 12).ch, ' ');
+        }
+
+        #[test]
+        fn denser_region_uses_heavier_glyph() {
+            let sparse = vec![blob(1, 'A', 3.0, config::DENSITY_SHADE_STEP)];
+            let dense: Vec<WordSnapshot> =
+                (1..=4).map(|id| blob(id, 'A', 3.0, config::DENSITY_SHADE_STEP)).collect();
+            let camera = Camera::default();
+            let viewport = Viewport { width: 80, height: 24 };
+            let glyph_level = |snapshot: &[WordSnapshot]| {
+                let mut frame = FrameBuffer::new(80, 24);
+                draw(snapshot, &[], None, &camera, viewport, &shaded(true), &mut frame);
+                let ch = frame.get(38, 12).ch;
+                DENSITY_GLYPHS.iter().position(|&g| g == ch).unwrap()
+            };
+
+            assert!(glyph_level(&dense) > glyph_level(&sparse));
+        }
+
+        #[test]
+        fn disabled_leaves_background_empty() {
+            let snapshot = vec![blob(1, 'A', 3.0, 50.0), blob(2, 'B', 3.0, 50.0)];
+            let camera = Camera::default();
+            let viewport = Viewport { width: 80, height: 24 };
+            let mut frame = FrameBuffer::new(80, 24);
+
+            draw(&snapshot, &[], None, &camera, viewport, &shaded(false), &mut frame);
+
+            assert_eq!(frame.get(38, 12).ch, ' ');
+            assert_eq!(frame.density(38, 12), 0.0);
+        }
+    }
+
+    mod world_overview {
+        use super::*;
+
+        fn labeled(id: WordId, label: &str, pos: Vec2, mass: f32) -> WordSnapshot {
+            let mut text = [' '; TEXT_MAX_DRAW];
+            let mut len = 0;
+            for (slot, ch) in text.iter_mut().zip(label.chars()) {
+                *slot = ch;
+                len += 1;
+            }
+            WordSnapshot {
+                id,
+                text,
+                text_len: len,
+                truncated: false,
+                display_width: text_display_width(&text[..len]),
+                pos,
+                radius: 1.0,
+                mass_visible: mass,
+                mass_total: mass,
+                mass_dust: 0.0,
+                vel: Vec2::ZERO,
+                trail: [pos; TRAIL_LEN],
+                trail_len: 0,
+                trail_head: 0,
+                merge_pending: false,
+                merge_count: 0,
+                split_count: 0,
+                age: 0.0,
+            }
+        }
+
+        #[test]
+        fn camera_fits_world_corners() {
+            let camera = overview_camera(80, 20);
+            let viewport = Viewport { width: 80, height: 20 };
+            let top_left = camera.screen_to_world(0.0, 0.0, viewport);
+            assert!(top_left.x <= -config::WORLD_HALF_WIDTH + 1e-3);
+            assert!(top_left.y <= -config::WORLD_HALF_HEIGHT + 1e-3);
+        }
+
+        #[test]
+        fn draws_every_visible_word_in_a_crowd() {
+            // 全角の一意な先頭文字を持つ語を1点付近に密集させる
+            let words: Vec<WordSnapshot> = (0..120u32)
+                .map(|i| {
+                    let ch = char::from_u32(0x4E00 + i).unwrap();
+                    let label: String = [ch, 'x'].iter().collect();
+                    let pos = Vec2::new((i % 7) as f32 * 0.5, (i / 7) as f32 * 0.3);
+                    labeled(i as WordId + 1, &label, pos, 1.0 + i as f32)
+                })
+                .collect();
+            let frame = render_world_overview(&words, 60, 20);
+
+            for i in 0..120u32 {
+                let ch = char::from_u32(0x4E00 + i).unwrap();
+                let found = (0..frame.height())
+                    .any(|y| (0..frame.width()).any(|x| frame.get(x, y).ch == ch));
+                assert!(found, "{ch} missing from overview");
+            }
+        }
+
+        #[test]
+        fn wide_chars_keep_their_continuation_cell() {
+            let words = vec![labeled(1, "星", Vec2::ZERO, 5.0)];
+            let frame = render_world_overview(&words, 40, 10);
+            let x = (0..40).find(|&x| frame.get(x, 5).ch == '星').unwrap();
+            assert_eq!(frame.get(x + 1, 5).ch, WIDE_CONTINUATION);
+            assert_eq!(frame.to_text().lines().nth(5).unwrap().trim(), "星");
+        }
+
+        #[test]
+        fn words_at_world_edges_are_drawn() {
+            let words = vec![
+                labeled(1, "L", Vec2::new(-config::WORLD_HALF_WIDTH, 0.0), 1.0),
+                labeled(2, "R", Vec2::new(config::WORLD_HALF_WIDTH, 0.0), 1.0),
+            ];
+            let text = render_world_overview(&words, 30, 8).to_text();
+            assert!(text.contains('L'));
+            assert!(text.contains('R'));
+        }
+    }
+
+    mod shockwave {
+        use super::*;
+
+        #[test]
+        fn draws_glyphs_on_the_current_radius() {
+            let ring = EffectParticle {
+                pos: Vec2::ZERO,
+                vel: Vec2::ZERO,
+                ttl: 1.0,
+                glyph: 'o',
+                color: ColorId::White,
+                kind: EffectKind::Shockwave {
+                    radius: 10.0,
+                    speed: 1.0,
+                    max_radius: 20.0,
+                },
+            };
+            let viewport = Viewport { width: 40, height: 24 };
+            let mut frame = FrameBuffer::new(40, 24);
+            let options = DrawOptions::default();
+            draw(&[], &[ring], None, &Camera::default(), viewport, &options, &mut frame);
+            // 半径 10 は横に 10 セル、縦はセルの縦横比で 5 行
+            for (x, y) in [(30, 12), (10, 12), (20, 7), (20, 17)] {
+                assert_eq!(frame.get(x, y).ch, 'o');
+                assert_eq!(frame.get(x, y).priority, EFFECT_PRIORITY);
+            }
+            assert_eq!(frame.get(20, 12).ch, ' ');
+        }
+    }
+
+    mod lod {
+        use super::*;
+
+        fn word(id: WordId, text: &str, pos: Vec2, mass: f32) -> WordSnapshot {
+            let mut chars = [' '; TEXT_MAX_DRAW];
+            for (slot, ch) in chars.iter_mut().zip(text.chars()) {
+                *slot = ch;
+            }
+            WordSnapshot {
+                id,
+                text: chars,
+                text_len: text.chars().count(),
+                truncated: false,
+                display_width: text_display_width(&chars[..text.chars().count()]),
+                pos,
+                radius: 1.0,
+                mass_visible: mass,
+                mass_total: mass,
+                mass_dust: 0.0,
+                vel: Vec2::ZERO,
+                trail: [Vec2::ZERO; TRAIL_LEN],
+                trail_len: 0,
+                trail_head: 0,
+                merge_pending: false,
+                merge_count: 0,
+                split_count: 0,
+                age: 0.0,
+            }
+        }
+
+        // 語数だけを増やすための画面外の語
+        fn crowd(words: &mut Vec<WordSnapshot>, count: usize) {
+            for i in 0..count {
+                words.push(word(1000 + i as WordId, "x", Vec2::new(10_000.0, 0.0), 1.0));
+            }
+        }
+
+        fn render(words: &[WordSnapshot], focus: Option<WordId>, lod: bool) -> FrameBuffer {
+            let viewport = Viewport { width: 80, height: 24 };
+            let mut frame = FrameBuffer::new(80, 24);
+            let options = DrawOptions {
+                lod,
+                ..DrawOptions::default()
+            };
+            draw(words, &[], focus, &Camera::default(), viewport, &options, &mut frame);
+            frame
+        }
+
+        #[test]
+        fn level_follows_word_count_and_zoom() {
+            assert_eq!(lod_level(100, 1.0), LodLevel::Full);
+            assert_eq!(lod_level(200, 1.0), LodLevel::Reduced);
+            assert_eq!(lod_level(400, 1.0), LodLevel::Minimal);
+            // 縮小すると同じ語数でも簡略化が進む
+            assert_eq!(lod_level(100, 0.5), LodLevel::Reduced);
+            assert_eq!(lod_level(200, 4.0), LodLevel::Full);
+        }
+
+        #[test]
+        fn reduced_draws_only_the_initial_of_far_light_words() {
+            let mut words = vec![
+                word(1, "far", Vec2::new(30.0, 0.0), 1.0),
+                word(2, "near", Vec2::new(-4.0, 0.0), 1.0),
+                word(3, "big", Vec2::new(30.0, 8.0), config::LOD_FULL_MASS),
+            ];
+            crowd(&mut words, 200);
+            let frame = render(&words, None, true);
+            assert_eq!(frame.get(70, 12).ch, 'f');
+            assert_eq!(frame.get(71, 12).ch, ' ');
+            assert_eq!(frame.get(36, 12).ch, 'n');
+            assert_eq!(frame.get(39, 12).ch, 'r');
+            assert_eq!(frame.get(72, 16).ch, 'g');
+        }
+
+        #[test]
+        fn minimal_draws_light_words_as_points_but_keeps_focus() {
+            let mut words = vec![
+                word(1, "near", Vec2::new(-4.0, 0.0), 1.0),
+                word(2, "focus", Vec2::new(-4.0, 8.0), 1.0),
+            ];
+            crowd(&mut words, 400);
+            let frame = render(&words, Some(2), true);
+            assert_eq!(frame.get(36, 12).ch, config::LOD_POINT_GLYPH);
+            assert_eq!(frame.get(37, 12).ch, ' ');
+            assert_eq!(frame.get(36, 16).ch, 'f');
+            assert_eq!(frame.get(40, 16).ch, 's');
+        }
+
+        #[test]
+        fn disabled_lod_draws_everything_in_full() {
+            let mut words = vec![word(1, "far", Vec2::new(30.0, 0.0), 1.0)];
+            crowd(&mut words, 400);
+            let frame = render(&words, None, false);
+            assert_eq!(frame.get(72, 12).ch, 'r');
+        }
+
+        fn trailed(mass: f32) -> WordSnapshot {
+            let mut w = word(1, "t", Vec2::ZERO, mass);
+            // 最新が x = -1、古いほど左
+            for k in 0..8 {
+                w.trail[k] = Vec2::new(-(8.0 - k as f32), 0.0);
+            }
+            w.trail_len = 8;
+            w.trail_head = 7;
+            w
+        }
+
+        #[test]
+        fn thins_and_drops_trails_of_light_words() {
+            let mut words = vec![trailed(1.0)];
+            crowd(&mut words, 200);
+            let frame = render(&words, None, true);
+            for x in [39, 37, 35, 33] {
+                assert_ne!(frame.get(x, 12).ch, ' ');
+            }
+            for x in [38, 36, 34, 32] {
+                assert_eq!(frame.get(x, 12).ch, ' ');
+            }
+
+            crowd(&mut words, 200);
+            let frame = render(&words, None, true);
+            assert!((32..40).all(|x| frame.get(x, 12).ch == ' '));
+
+            words[0] = trailed(config::LOD_FULL_MASS);
+            let frame = render(&words, None, true);
+            assert!((32..40).all(|x| frame.get(x, 12).ch != ' '));
+        }
+    }
+
+    mod cell_cap {
+        use super::*;
+
+        fn word(id: WordId, text_str: &str, pos: Vec2) -> WordSnapshot {
+            let mut text = [' '; TEXT_MAX_DRAW];
+            for (slot, ch) in text.iter_mut().zip(text_str.chars()) {
+                *slot = ch;
+            }
+            let text_len = text_str.chars().count();
+            WordSnapshot {
+                id,
+                text,
+                text_len,
+                truncated: false,
+                display_width: text_display_width(&text[..text_len]),
+                pos,
+                radius: 1.0,
+                mass_visible: 1.0,
+                mass_total: 1.0,
+                mass_dust: 0.0,
+                vel: Vec2::ZERO,
+                trail: [Vec2::ZERO; TRAIL_LEN],
+                trail_len: 0,
+                trail_head: 0,
+                merge_pending: false,
+                merge_count: 0,
+                split_count: 0,
+                age: 0.0,
+            }
+        }
+
+        fn render(snapshot: &[WordSnapshot], focus: Option<WordId>, cap: usize) -> FrameBuffer {
+            let viewport = Viewport { width: 40, height: 12 };
+            let mut frame = FrameBuffer::new(40, 12);
+            let options = DrawOptions {
+                max_cells_per_word: cap,
+                ..DrawOptions::default()
+            };
+            draw(snapshot, &[], focus, &Camera::default(), viewport, &options, &mut frame);
+            frame
+        }
+
+        fn row(frame: &FrameBuffer, y: u16, from: u16, to: u16) -> String {
+            (from..to).map(|x| frame.get(x, y).ch).collect()
+        }
+
+        #[test]
+        fn cuts_long_words_with_an_ellipsis() {
+            let frame = render(&[word(1, "abcdefghij", Vec2::ZERO)], None, 5);
+            assert_eq!(row(&frame, 6, 20, 27), "abcd…  ");
+        }
+
+        #[test]
+        fn counts_fullwidth_characters_as_two_cells() {
+            let frame = render(&[word(1, "漢字漢字漢字", Vec2::ZERO)], None, 6);
+            assert_eq!(frame.get(20, 6).ch, '漢');
+            assert_eq!(frame.get(22, 6).ch, '字');
+            assert_eq!(frame.get(24, 6).ch, ELLIPSIS);
+            assert_eq!(frame.get(25, 6).ch, ' ');
+        }
+
+        #[test]
+        fn leaves_short_words_and_zero_cap_alone() {
+            let short = word(1, "abcde", Vec2::ZERO);
+            assert!(matches!(capped_word(&short, 5), Cow::Borrowed(_)));
+            let long = word(1, "abcdefghij", Vec2::ZERO);
+            assert!(matches!(capped_word(&long, 0), Cow::Borrowed(_)));
+            let frame = render(&[long], None, 0);
+            assert_eq!(row(&frame, 6, 20, 30), "abcdefghij");
+        }
+
+        #[test]
+        fn focused_word_is_not_capped() {
+            let frame = render(&[word(1, "abcdefghij", Vec2::ZERO)], Some(1), 5);
+            assert_eq!(row(&frame, 6, 20, 30), "abcdefghij");
+        }
+
+        #[test]
+        fn bounds_cells_for_many_long_words() {
+            let long = "x".repeat(30);
+            // 1行ずつ離して縦に並べる
+            let words: Vec<_> = (0..5)
+                .map(|i| word(i + 1, &long, Vec2::new(-20.0, (i as f32 - 2.0) * 4.0)))
+                .collect();
+            let count = |frame: &FrameBuffer| {
+                (0..12)
+                    .flat_map(|y| (0..40).map(move |x| (x, y)))
+                    .filter(|&(x, y)| frame.get(x, y).ch != ' ')
+                    .count()
+            };
+            assert_eq!(count(&render(&words, None, 0)), 5 * 30);
+            assert_eq!(count(&render(&words, None, 8)), 5 * 8);
+        }
+    }
+
+    mod offscreen_indicators {
+        use super::*;
+
+        fn word(id: WordId, pos: Vec2, mass: f32) -> WordSnapshot {
+            let mut text = [' '; TEXT_MAX_DRAW];
+            text[0] = 'w';
+            WordSnapshot {
+                id,
+                text,
+                text_len: 1,
+                truncated: false,
+                display_width: 1,
+                pos,
+                radius: 1.0,
+                mass_visible: mass,
+                mass_total: mass,
+                mass_dust: 0.0,
+                vel: Vec2::ZERO,
+                trail: [Vec2::ZERO; TRAIL_LEN],
+                trail_len: 0,
+                trail_head: 0,
+                merge_pending: false,
+                merge_count: 0,
+                split_count: 0,
+                age: 0.0,
+            }
+        }
+
+        fn render(snapshot: &[WordSnapshot], focus: Option<WordId>, max: usize) -> FrameBuffer {
+            let viewport = Viewport { width: 40, height: 12 };
+            let mut frame = FrameBuffer::new(40, 12);
+            let options = DrawOptions {
+                offscreen_indicators: max,
+                offscreen_min_mass: 10.0,
+                ..DrawOptions::default()
+            };
+            draw(snapshot, &[], focus, &Camera::default(), viewport, &options, &mut frame);
+            frame
+        }
+
+        fn arrows(frame: &FrameBuffer) -> Vec<(u16, u16, char)> {
+            let mut out = Vec::new();
+            for y in 0..12 {
+                for x in 0..40 {
+                    let ch = frame.get(x, y).ch;
+                    if OFFSCREEN_ARROWS.contains(&ch) {
+                        out.push((x, y, ch));
+                    }
+                }
+            }
+            out
+        }
+
+        #[test]
+        fn points_from_the_edge_toward_the_word() {
+            let frame = render(&[word(1, Vec2::new(100.0, 0.0), 50.0)], None, 4);
+            assert_eq!(arrows(&frame), vec![(39, 6, '→')]);
+            // 画面上で左上 45° にある語
+            let pos = Vec2::new(-100.0, -100.0 * config::CELL_ASPECT);
+            let frame = render(&[word(1, pos, 50.0)], None, 4);
+            assert_eq!(arrows(&frame), vec![(14, 0, '↖')]);
+            let frame = render(&[word(1, Vec2::new(0.0, 80.0), 50.0)], None, 4);
+            assert_eq!(arrows(&frame), vec![(20, 11, '↓')]);
+        }
+
+        #[test]
+        fn skips_light_and_visible_words() {
+            let snapshot = [
+                word(1, Vec2::new(100.0, 0.0), 5.0),
+                word(2, Vec2::new(5.0, 0.0), 50.0),
+            ];
+            assert!(arrows(&render(&snapshot, None, 4)).is_empty());
+            assert!(arrows(&render(&[word(1, Vec2::new(100.0, 0.0), 50.0)], None, 0)).is_empty());
+        }
+
+        #[test]
+        fn keeps_the_nearest_and_heaviest_up_to_the_limit() {
+            let snapshot = [
+                word(1, Vec2::new(-200.0, 0.0), 50.0),
+                word(2, Vec2::new(60.0, 0.0), 50.0),
+                word(3, Vec2::new(0.0, -200.0), 400.0),
+            ];
+            let frame = render(&snapshot, None, 2);
+            let shown: Vec<char> = arrows(&frame).iter().map(|a| a.2).collect();
+            assert_eq!(shown.len(), 2);
+            assert!(shown.contains(&'→') && shown.contains(&'↑'), "{shown:?}");
+        }
+
+        #[test]
+        fn marks_the_focus_word_in_red_even_when_light() {
+            let snapshot = [
+                word(1, Vec2::new(100.0, 0.0), 1.0),
+                word(2, Vec2::new(-60.0, 0.0), 50.0),
+            ];
+            let frame = render(&snapshot, Some(1), 1);
+            assert_eq!(arrows(&frame), vec![(39, 6, '→')]);
+            assert_eq!(frame.get(39, 6).color, ColorId::Red);
+        }
     }
 }
@@ -0,0 +1,132 @@
+use serde_json::Value;
+
+use crate::core::World;
+
+pub const SHARE_PREFIX: &str = "wordcosmo2:";
+const SHARE_VERSION: u32 = 1;
+
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+// 貼り付けて共有できる1行の状態文字列。"wordcosmo2:<版>:" に save_json の base64 を続ける
+pub fn share_text(world: &World) -> String {
+    let json = world.save_json().to_string();
+    format!("{SHARE_PREFIX}{SHARE_VERSION}:{}", base64_encode(json.as_bytes()))
+}
+
+// share_text の文字列で全語を置き換える。前後の空白は無視する
+pub fn restore_share_text(world: &mut World, text: &str) -> Result<(), String> {
+    let body = text
+        .trim()
+        .strip_prefix(SHARE_PREFIX)
+        .ok_or_else(|| "not a shared world".to_string())?;
+    let (version, payload) = body
+        .split_once(':')
+        .ok_or_else(|| "missing share version".to_string())?;
+    if version != SHARE_VERSION.to_string() {
+        return Err(format!("unsupported share version {version}"));
+    }
+    let bytes = base64_decode(payload).ok_or_else(|| "broken share data".to_string())?;
+    let value: Value = serde_json::from_slice(&bytes).map_err(|err| err.to_string())?;
+    world.restore_json(&value)
+}
+
+// 標準 base64（パディングあり）
+fn base64_encode(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let b = [chunk[0], *chunk.get(1).unwrap_or(&0), *chunk.get(2).unwrap_or(&0)];
+        let n = (b[0] as u32) << 16 | (b[1] as u32) << 8 | b[2] as u32;
+        for k in 0..4 {
+            if k <= chunk.len() {
+                out.push(BASE64_ALPHABET[(n >> (18 - 6 * k) & 0x3f) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+// 長さが4の倍数でない・範囲外の文字を含むなら None
+fn base64_decode(text: &str) -> Option<Vec<u8>> {
+    let text = text.as_bytes();
+    if !text.len().is_multiple_of(4) {
+        return None;
+    }
+    let mut out = Vec::with_capacity(text.len() / 4 * 3);
+    let last = text.len() / 4;
+    for (i, chunk) in text.chunks(4).enumerate() {
+        let pad = chunk.iter().rev().take_while(|&&c| c == b'=').count();
+        if pad > 2 || (pad > 0 && i + 1 != last) {
+            return None;
+        }
+        let mut n = 0u32;
+        for &c in &chunk[..4 - pad] {
+            let v = BASE64_ALPHABET.iter().position(|&a| a == c)? as u32;
+            n = n << 6 | v;
+        }
+        n <<= 6 * pad as u32;
+        out.extend_from_slice(&n.to_be_bytes()[1..4 - pad]);
+    }
+    Some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config;
+
+    mod share_text {
+        use super::*;
+
+        #[test]
+        fn base64_matches_known_vectors() {
+            let cases =
+                [("", ""), ("f", "Zg=="), ("fo", "Zm8="), ("foo", "Zm9v"), ("foob", "Zm9vYg==")];
+            for (plain, encoded) in cases {
+                assert_eq!(base64_encode(plain.as_bytes()), encoded);
+                assert_eq!(base64_decode(encoded).unwrap(), plain.as_bytes());
+            }
+            let bytes: Vec<u8> = (0..=255).collect();
+            assert_eq!(base64_decode(&base64_encode(&bytes)).unwrap(), bytes);
+            for broken in ["Zg=", "Z===", "Zg==Zm9v", "Zm9*"] {
+                assert!(base64_decode(broken).is_none(), "{broken}");
+            }
+        }
+
+        #[test]
+        fn round_trip_keeps_mass_and_visible_words() {
+            let mut world = World::new_with_seed(12);
+            for _ in 0..30 {
+                world.tick(config::DT);
+            }
+            let text = share_text(&world);
+            assert!(text.starts_with("wordcosmo2:1:"));
+            assert!(!text.contains('\n'));
+
+            let mut restored = World::new_with_seed(3);
+            restore_share_text(&mut restored, &format!("  {text}\n")).unwrap();
+            let (before, after) = (world.stats(), restored.stats());
+            assert_eq!(after.visible_count, before.visible_count);
+            assert_eq!(after.total_words, before.total_words);
+            assert!((after.total_mass - before.total_mass).abs() < 1e-3);
+        }
+
+        #[test]
+        fn rejects_other_versions_and_garbage() {
+            let world = World::new_with_seed(12);
+            let text = share_text(&world);
+            let mut target = World::new_with_seed(3);
+            let words = target.stats().total_words;
+
+            let future = text.replacen("wordcosmo2:1:", "wordcosmo2:2:", 1);
+            let err = restore_share_text(&mut target, &future).unwrap_err();
+            assert!(err.contains("version"));
+            assert!(restore_share_text(&mut target, "hello").is_err());
+            assert!(restore_share_text(&mut target, "wordcosmo2:1:@@@@").is_err());
+            assert!(restore_share_text(&mut target, "wordcosmo2:1:e30=").is_err());
+            assert_eq!(target.stats().total_words, words);
+        }
+    }
+}
@@ -1,11 +1,19 @@
 use std::collections::HashMap;
 
-use crate::types::Vec2;
+use crate::{config, types::Vec2};
 
 #[derive(Debug)]
 pub struct SpatialHash {
     cell_size: f32,
-    cells: HashMap<(i32, i32), Vec<usize>>,
+    // 空になったセルも Vec ごと残して次の rebuild で使い回す
+    cells: HashMap<(i32, i32), Cell>,
+}
+
+#[derive(Debug, Default)]
+struct Cell {
+    indices: Vec<usize>,
+    // 空のまま続いた rebuild の回数
+    idle: u32,
 }
 
 impl SpatialHash {
@@ -25,11 +33,23 @@ impl SpatialHash {
     }
 
     pub fn rebuild(&mut self, positions: &[Vec2]) {
-        self.cells.clear();
+        for cell in self.cells.values_mut() {
+            cell.indices.clear();
+        }
         for (idx, pos) in positions.iter().enumerate() {
             let key = self.cell_key(*pos);
-            self.cells.entry(key).or_default().push(idx);
+            self.cells.entry(key).or_default().indices.push(idx);
         }
+        // 語が去ったセルはしばらく残し、戻ってこなければ回収する
+        self.cells.retain(|_, cell| {
+            if cell.indices.is_empty() {
+                cell.idle += 1;
+                cell.idle <= config::SPATIAL_CELL_IDLE_REBUILDS
+            } else {
+                cell.idle = 0;
+                true
+            }
+        });
     }
 
     pub fn query_neighbors(&self, pos: Vec2, out: &mut Vec<usize>) {
@@ -43,14 +63,62 @@ impl SpatialHash {
         for dy in -range..=range {
             for dx in -range..=range {
                 let key = (cx + dx, cy + dy);
-                if let Some(indices) = self.cells.get(&key) {
-                    out.extend_from_slice(indices);
+                if let Some(cell) = self.cells.get(&key) {
+                    out.extend_from_slice(&cell.indices);
+                }
+            }
+        }
+    }
+
+    // weight(index) の合計が最大のセルの中心と合計値。同値ならキーの小さいセルを選ぶ
+    // （HashMap の走査順に結果を左右させない）。
+    pub fn densest_cell(&self, weight: impl Fn(usize) -> f32) -> Option<(Vec2, f32)> {
+        let mut best: Option<((i32, i32), f32)> = None;
+        for (&key, cell) in &self.cells {
+            if cell.indices.is_empty() {
+                continue;
+            }
+            let indices = &cell.indices;
+            let total: f32 = indices.iter().map(|&idx| weight(idx)).sum();
+            let better = match best {
+                None => true,
+                Some((best_key, best_total)) => {
+                    total > best_total || (total == best_total && key < best_key)
                 }
+            };
+            if better {
+                best = Some((key, total));
+            }
+        }
+        best.map(|((cx, cy), total)| {
+            let center = Vec2::new(
+                (cx as f32 + 0.5) * self.cell_size,
+                (cy as f32 + 0.5) * self.cell_size,
+            );
+            (center, total)
+        })
+    }
+
+    // 語のあるセルごとの weight(index) の合計で out を置き換える
+    pub fn cell_sums(&self, weight: impl Fn(usize) -> f32, out: &mut HashMap<(i32, i32), f32>) {
+        out.clear();
+        for (&key, cell) in &self.cells {
+            if !cell.indices.is_empty() {
+                out.insert(key, cell.indices.iter().map(|&idx| weight(idx)).sum());
             }
         }
     }
 
-    fn cell_key(&self, pos: Vec2) -> (i32, i32) {
+    // セルの最小角（ワールド座標）
+    pub fn cell_origin(&self, (cx, cy): (i32, i32)) -> Vec2 {
+        Vec2::new(cx as f32 * self.cell_size, cy as f32 * self.cell_size)
+    }
+
+    pub fn cell_size(&self) -> f32 {
+        self.cell_size
+    }
+
+    pub fn cell_key(&self, pos: Vec2) -> (i32, i32) {
         let cx = (pos.x / self.cell_size).floor() as i32;
         let cy = (pos.y / self.cell_size).floor() as i32;
         (cx, cy)
@@ -114,7 +182,7 @@ mod tests {
             ];
             hash.rebuild(&positions);
             let cell = hash.cells.get(&(0, 0)).expect("Cell (0,0) should exist");
-            assert_eq!(cell.len(), 3);
+            assert_eq!(cell.indices.len(), 3);
         }
 
         #[test]
@@ -122,8 +190,42 @@ mod tests {
             let mut hash = SpatialHash::new(10.0);
             hash.rebuild(&[Vec2::new(5.0, 5.0)]);
             hash.rebuild(&[Vec2::new(15.0, 15.0)]);
-            assert!(hash.cells.get(&(0, 0)).is_none());
-            assert!(hash.cells.get(&(1, 1)).is_some());
+            assert!(hash.cells.get(&(0, 0)).is_none_or(|cell| cell.indices.is_empty()));
+            assert_eq!(hash.cells[&(1, 1)].indices, [0]);
+        }
+
+        #[test]
+        fn vacated_cells_hold_no_stale_indices() {
+            let mut hash = SpatialHash::new(10.0);
+            hash.rebuild(&[Vec2::new(5.0, 5.0), Vec2::new(6.0, 6.0)]);
+            hash.rebuild(&[Vec2::new(25.0, 5.0)]);
+            let mut out = Vec::new();
+            hash.query_neighbors_range(Vec2::new(5.0, 5.0), 0, &mut out);
+            assert!(out.is_empty());
+            hash.query_neighbors_range(Vec2::new(25.0, 5.0), 0, &mut out);
+            assert_eq!(out, [0]);
+            assert!(hash.densest_cell(|_| 0.0).is_some_and(|(c, _)| c == Vec2::new(25.0, 5.0)));
+        }
+
+        #[test]
+        fn reuses_cells_and_drops_long_idle_ones() {
+            let mut hash = SpatialHash::new(10.0);
+            let home = [Vec2::new(5.0, 5.0)];
+            let away = [Vec2::new(55.0, 5.0)];
+            hash.rebuild(&home);
+            let capacity = hash.cells[&(0, 0)].indices.capacity();
+            hash.rebuild(&away);
+            assert_eq!(hash.cells[&(0, 0)].indices.capacity(), capacity);
+            hash.rebuild(&home);
+            assert_eq!(hash.cells[&(0, 0)].idle, 0);
+
+            for _ in 0..config::SPATIAL_CELL_IDLE_REBUILDS {
+                hash.rebuild(&away);
+            }
+            assert!(hash.cells.contains_key(&(0, 0)));
+            hash.rebuild(&away);
+            assert!(!hash.cells.contains_key(&(0, 0)));
+            assert_eq!(hash.cells.len(), 1);
         }
     }
 
@@ -225,4 +327,58 @@ mod tests {
             assert!(hash.cells.is_empty());
         }
     }
+
+    mod spatial_hash_cell_sums {
+        use super::*;
+
+        #[test]
+        fn sums_weights_per_occupied_cell() {
+            let mut hash = SpatialHash::new(10.0);
+            hash.rebuild(&[Vec2::new(1.0, 1.0), Vec2::new(2.0, 2.0), Vec2::new(-5.0, 15.0)]);
+            // 空になったセルは残っていても含めない
+            hash.rebuild(&[Vec2::new(1.0, 1.0), Vec2::new(2.0, 2.0)]);
+            let mut out = HashMap::from([((9, 9), 1.0)]);
+            hash.cell_sums(|idx| [3.0, 4.0][idx], &mut out);
+            assert_eq!(out, HashMap::from([((0, 0), 7.0)]));
+            assert_eq!(hash.cell_origin((-1, 1)), Vec2::new(-10.0, 10.0));
+        }
+    }
+
+    mod spatial_hash_densest_cell {
+        use super::*;
+
+        #[test]
+        fn empty_hash_has_no_densest_cell() {
+            let hash = SpatialHash::new(10.0);
+            assert!(hash.densest_cell(|_| 1.0).is_none());
+        }
+
+        #[test]
+        fn picks_cell_with_largest_weight_sum() {
+            let mut hash = SpatialHash::new(10.0);
+            let positions = [
+                Vec2::new(1.0, 1.0),
+                Vec2::new(2.0, 2.0),
+                Vec2::new(25.0, 5.0),
+            ];
+            hash.rebuild(&positions);
+            let weights = [1.0, 1.0, 5.0];
+
+            let (center, total) = hash.densest_cell(|idx| weights[idx]).unwrap();
+            assert_eq!(center, Vec2::new(25.0, 5.0));
+            assert_eq!(total, 5.0);
+
+            let (center, total) = hash.densest_cell(|_| 1.0).unwrap();
+            assert_eq!(center, Vec2::new(5.0, 5.0));
+            assert_eq!(total, 2.0);
+        }
+
+        #[test]
+        fn ties_break_by_cell_key() {
+            let mut hash = SpatialHash::new(10.0);
+            hash.rebuild(&[Vec2::new(15.0, 0.0), Vec2::new(-15.0, 0.0)]);
+            let (center, _) = hash.densest_cell(|_| 1.0).unwrap();
+            assert_eq!(center, Vec2::new(-15.0, 5.0));
+        }
+    }
 }
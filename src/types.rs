@@ -1,5 +1,8 @@
+use std::collections::HashMap;
 use std::ops::{Add, AddAssign, Mul, Sub, SubAssign};
 
+use unicode_width::UnicodeWidthChar;
+
 #[derive(Clone, Copy, Debug, Default, PartialEq)]
 pub struct Vec2 {
     pub x: f32,
@@ -33,6 +36,32 @@ impl Vec2 {
     pub fn dot(self, other: Vec2) -> f32 {
         self.x * other.x + self.y * other.y
     }
+
+    pub fn clamp_length(self, max: f32) -> Vec2 {
+        let len_sq = self.length_sq();
+        if len_sq > max * max && len_sq > 0.0 {
+            self * (max.max(0.0) / len_sq.sqrt())
+        } else {
+            self
+        }
+    }
+
+    // other 方向の成分だけを取り出す。other がゼロなら ZERO。
+    pub fn project_onto(self, other: Vec2) -> Vec2 {
+        let denom = other.length_sq();
+        if denom > 0.0 {
+            other * (self.dot(other) / denom)
+        } else {
+            Vec2::ZERO
+        }
+    }
+
+    /// 法線 `normal` を持つ面で鏡面反射したベクトルを返す。
+    ///
+    /// `normal` は単位ベクトルであること（正規化はしない）。
+    pub fn reflect(self, normal: Vec2) -> Vec2 {
+        self - normal * (2.0 * self.dot(normal))
+    }
 }
 
 impl Add for Vec2 {
@@ -85,6 +114,21 @@ pub type WordId = u64;
 
 pub const TEXT_MAX_DRAW: usize = 120;
 pub const TRAIL_LEN: usize = 10;
+pub const SPEED_HISTOGRAM_BINS: usize = 8;
+
+// 結合文字・異体字セレクタ・ZWJ など幅 0 の文字は 0。直前の文字と同じセルに重ねて表示する
+pub fn char_cell_width(ch: char) -> usize {
+    match ch.width() {
+        Some(0) if !ch.is_control() => 0,
+        Some(width) if width >= 2 => 2,
+        _ => 1,
+    }
+}
+
+// 文字列を並べたときの合計セル幅
+pub fn text_display_width(text: &[char]) -> u16 {
+    text.iter().map(|&ch| char_cell_width(ch) as u16).sum()
+}
 
 #[derive(Clone, Debug)]
 pub struct Word {
@@ -100,11 +144,22 @@ pub struct Word {
     pub trail: [Vec2; TRAIL_LEN],
     pub trail_head: usize,
     pub trail_len: usize,
+    // 生成（合体・分裂による再生成を含む）からの経過秒数
+    pub age: f32,
+    // 祖先を通じた合体・分裂の回数。合体語は元の語の合計 + 1、破片は親の値を引き継ぐ
+    pub merge_count: u32,
+    pub split_count: u32,
+    // 重力源としての質量と、加速されるときの慣性質量に掛ける倍率。両方 1.0 で通常の重力。
+    // mass_total そのものは変えない
+    pub gravity_mass_scale: f32,
+    pub inertia_mass_scale: f32,
 }
 
 #[derive(Clone, Copy, Debug, Default)]
 pub struct WordFlags {
     pub can_split: bool,
+    // 合体・分裂しない観測用の語。衝突の反発は通常通り
+    pub inert: bool,
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -117,7 +172,13 @@ pub enum ColorId {
     Red,
     Gray,
     Trail,
+    Prediction,
     Spark,
+    Highlight,
+    Shade,
+    MergeWarning,
+    // トゥルーカラー。非対応端末では表示側で近似色に落とす。
+    Rgb(u8, u8, u8),
 }
 
 #[derive(Clone, Copy, Debug)]
@@ -125,6 +186,9 @@ pub struct WordSnapshot {
     pub id: WordId,
     pub text: [char; TEXT_MAX_DRAW],
     pub text_len: usize,
+    pub truncated: bool,
+    // text[..text_len] の合計セル幅（全角は2、幅 0 の文字は0）。省略記号は含まない
+    pub display_width: u16,
     pub pos: Vec2,
     pub radius: f32,
     pub mass_visible: f32,
@@ -134,6 +198,19 @@ pub struct WordSnapshot {
     pub trail: [Vec2; TRAIL_LEN],
     pub trail_len: usize,
     pub trail_head: usize,
+    // 合体の猶予期間中（接近警告）
+    pub merge_pending: bool,
+    pub merge_count: u32,
+    pub split_count: u32,
+    pub age: f32,
+}
+
+// 1回の合体の記録。sources は合体直前の元の語、pos は合体語が生まれた位置
+#[derive(Clone, Debug)]
+pub struct MergeRecord {
+    pub merged: WordId,
+    pub pos: Vec2,
+    pub sources: Vec<WordSnapshot>,
 }
 
 #[derive(Clone, Copy, Debug)]
@@ -143,21 +220,60 @@ pub struct EffectParticle {
     pub ttl: f32,
     pub glyph: char,
     pub color: ColorId,
+    pub kind: EffectKind,
 }
 
-#[derive(Clone, Copy, Debug, Default)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum EffectKind {
+    // pos の1セルに glyph を出す粒子
+    #[default]
+    Particle,
+    // pos を中心に radius の円周上へ glyph を並べるリング。speed で広がり max_radius で消える
+    Shockwave { radius: f32, speed: f32, max_radius: f32 },
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
 pub struct WorldStats {
     pub visible_count: usize,
     pub dust_count: usize,
     pub total_words: usize,
     pub total_mass_visible: f32,
     pub total_mass: f32,
+    // 初期語と投入語の質量の累計と、それに対する total_mass の差
+    pub mass_budget: f32,
+    pub mass_drift: f32,
     pub gravity_candidates_avg: f32,
     pub collision_candidates_avg: f32,
+    // 直近 tick に重力・衝突で行った近傍クエリの回数
+    pub neighbor_queries: usize,
+    // 直近 tick に語の索引を全走査で張り直した回数
+    pub index_rebuilds: usize,
+    // エフェクトのリングバッファの埋まり具合（len / capacity）と、直近 tick に積んだ数・
+    // 満杯で古いものを上書きした数
+    pub effect_usage: f32,
+    pub effect_spawns: usize,
+    pub effect_overflows: usize,
     pub gravity_debug: GravityDebugStats,
+    pub speed_histogram: SpeedHistogram,
+    pub spawn_queue_len: usize,
+    pub tick: u64,
+    // 生成または直近の clear からの tick 数と dt の累計
+    pub elapsed_ticks: u64,
+    pub elapsed_time: f32,
+    pub kinetic_energy: f32,
+    // 生成または直近の clear からの累計
+    pub merges: u64,
+    pub splits: u64,
 }
 
-#[derive(Clone, Copy, Debug, Default)]
+// 0〜max_speed を等分したビンごとの語数。語が無ければ全ビン 0、max_speed も 0。
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct SpeedHistogram {
+    pub bins: [usize; SPEED_HISTOGRAM_BINS],
+    pub max_speed: f32,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
 pub struct GravityDebugStats {
     pub sample_index: i32,
     pub candidates: usize,
@@ -170,6 +286,20 @@ pub struct GravityDebugStats {
     pub sample_other_subvisible: bool,
 }
 
+// 合体・分裂の起きた場所の集計。値は時間減衰を掛けた回数
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ActivityCell {
+    pub merges: f32,
+    pub splits: f32,
+}
+
+// 空間ハッシュと同じセル分割での ActivityCell の表
+#[derive(Clone, Debug, Default)]
+pub struct ActivityMap {
+    pub cell_size: f32,
+    pub cells: HashMap<(i32, i32), ActivityCell>,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -298,6 +428,29 @@ mod tests {
         }
     }
 
+    mod char_cell_width_fn {
+        use super::*;
+
+        #[test]
+        fn ascii_is_one_cell() {
+            assert_eq!(char_cell_width('a'), 1);
+        }
+
+        #[test]
+        fn fullwidth_is_two_cells() {
+            assert_eq!(char_cell_width('語'), 2);
+            assert_eq!(char_cell_width('Ａ'), 2);
+        }
+
+        #[test]
+        fn combining_marks_take_no_cell() {
+            assert_eq!(char_cell_width('\u{3099}'), 0);
+            assert_eq!(char_cell_width('\u{301}'), 0);
+            assert_eq!(char_cell_width('\u{FE0F}'), 0);
+            assert_eq!(char_cell_width('\0'), 1);
+        }
+    }
+
     mod vec2_mul {
         use super::*;
 
@@ -324,4 +477,73 @@ mod tests {
             assert_eq!(result, Vec2::ZERO);
         }
     }
+
+    mod vec2_clamp_length {
+        use super::*;
+
+        #[test]
+        fn long_vector_is_shortened_keeping_direction() {
+            let v = Vec2::new(6.0, 8.0).clamp_length(5.0);
+            assert!((v.length() - 5.0).abs() < 1e-6);
+            assert!((v.x - 3.0).abs() < 1e-6);
+            assert!((v.y - 4.0).abs() < 1e-6);
+        }
+
+        #[test]
+        fn short_vector_is_unchanged() {
+            let v = Vec2::new(1.0, 2.0);
+            assert_eq!(v.clamp_length(5.0), v);
+        }
+
+        #[test]
+        fn zero_vector_stays_zero() {
+            assert_eq!(Vec2::ZERO.clamp_length(0.0), Vec2::ZERO);
+        }
+    }
+
+    mod vec2_project_onto {
+        use super::*;
+
+        #[test]
+        fn removes_orthogonal_component() {
+            let p = Vec2::new(3.0, 4.0).project_onto(Vec2::new(2.0, 0.0));
+            assert_eq!(p, Vec2::new(3.0, 0.0));
+        }
+
+        #[test]
+        fn remainder_is_orthogonal_to_axis() {
+            let v = Vec2::new(2.0, 5.0);
+            let axis = Vec2::new(1.0, 1.0);
+            let rest = v - v.project_onto(axis);
+            assert!(rest.dot(axis).abs() < 1e-6);
+        }
+
+        #[test]
+        fn zero_axis_gives_zero() {
+            assert_eq!(Vec2::new(1.0, 2.0).project_onto(Vec2::ZERO), Vec2::ZERO);
+        }
+    }
+
+    mod vec2_reflect {
+        use super::*;
+
+        #[test]
+        fn angle_of_incidence_equals_angle_of_reflection() {
+            let normal = Vec2::new(0.0, 1.0);
+            let incoming = Vec2::new(3.0, -4.0);
+            let reflected = incoming.reflect(normal);
+            assert_eq!(reflected, Vec2::new(3.0, 4.0));
+            // 法線との角度（の余弦）が入射と反射で一致し、向きだけが逆になる
+            let cos_in = -incoming.dot(normal) / incoming.length();
+            let cos_out = reflected.dot(normal) / reflected.length();
+            assert!((cos_in - cos_out).abs() < 1e-6);
+        }
+
+        #[test]
+        fn preserves_length() {
+            let normal = Vec2::new(1.0, 1.0).normalize();
+            let v = Vec2::new(2.0, -7.0);
+            assert!((v.reflect(normal).length() - v.length()).abs() < 1e-5);
+        }
+    }
 }
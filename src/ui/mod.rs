@@ -1,47 +1,110 @@
-use std::{cmp::Ordering, collections::HashMap, error::Error, io, mem, time::Duration};
+use std::{
+    cell::Cell,
+    cmp::Ordering,
+    collections::HashMap,
+    error::Error,
+    io::{self, Write},
+    mem,
+    path::Path,
+    rc::Rc,
+    time::{Duration, Instant},
+};
 
 use crossterm::{
-    event::{self, Event as CrosstermEvent, KeyCode},
+    event::{
+        self, DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste, EnableMouseCapture,
+        Event as CrosstermEvent, KeyCode, MouseButton, MouseEvent, MouseEventKind,
+    },
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
 use ratatui::{
     backend::CrosstermBackend,
-    layout::{Constraint, Direction, Layout},
+    layout::{Constraint, Direction, Layout, Rect},
     style::{Color, Style},
-    text::{Line, Span},
+    text::{Line, Span, Text},
     widgets::{Block, Borders, Paragraph},
     Terminal,
 };
 
 use crate::{
-    config,
-    core::World,
-    render,
-    types::{ColorId, EffectParticle, Vec2, WordId, WordSnapshot},
+    cli::CliOptions,
+    config::{self, LayerOrder, SpawnMode},
+    core::{self, World},
+    render, share,
+    types::{
+        char_cell_width, ActivityMap, ColorId, MergeRecord, Vec2, WordId, WordSnapshot, WorldStats,
+    },
 };
 
-pub fn run() -> Result<(), Box<dyn Error>> {
+pub fn run(options: CliOptions) -> Result<(), Box<dyn Error>> {
     enable_raw_mode()?;
     let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen)?;
+    execute!(stdout, EnterAlternateScreen, EnableMouseCapture, EnableBracketedPaste)?;
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
     let result: Result<(), Box<dyn Error>> = (|| {
-        let mut world = World::new();
+        let mut world =
+            World::with_init_mass_dist(core::default_rng(options.seed), options.init_mass_dist);
+        world.set_physics_only(options.physics_only);
+        world.set_record_merges(config::MERGE_ANIM_SECS > 0.0);
+        world.set_time_travel(
+            config::TIME_TRAVEL_INTERVAL_TICKS,
+            config::TIME_TRAVEL_CHECKPOINTS_MAX,
+        );
+        if let Some(path) = &options.event_log {
+            world.set_event_log(Some(Box::new(std::fs::File::create(path)?)));
+        }
+        if let Some(path) = &options.schedule {
+            for entry in core::parse_schedule(&std::fs::read_to_string(path)?)? {
+                world.schedule_word(entry.at_tick, entry.text, entry.mass, entry.pos);
+            }
+        }
+        // 物理側はオブザーバに知らせるだけで、ベルを鳴らすかはここで決める
+        let notices = Rc::new(Cell::new(0_usize));
+        if config::SOUND_NOTIFY {
+            let sink = Rc::clone(&notices);
+            world.set_observer(Some(Box::new(move |_| sink.set(sink.get() + 1))));
+        }
+        let mut telemetry = match &options.telemetry {
+            Some(path) if options.telemetry_interval_ms > 0 => {
+                let out: Box<dyn Write> = if path == "-" {
+                    Box::new(io::stderr())
+                } else {
+                    Box::new(io::BufWriter::new(std::fs::File::create(path)?))
+                };
+                Some(Telemetry::new(out, Duration::from_millis(options.telemetry_interval_ms)))
+            }
+            _ => None,
+        };
+        let mut bell = BellThrottle::default();
+        let bell_interval = Duration::from_millis(config::SOUND_THROTTLE_MS);
         let mut snapshot: Vec<WordSnapshot> = Vec::with_capacity(config::K_VISIBLE_MAX);
         let mut ui_state = UiState::new();
-        let mut effects: Vec<EffectParticle> = Vec::with_capacity(config::EFFECT_CAPACITY);
+        if !world.config_warnings().is_empty() {
+            ui_state.status = format!("config warning: {}", world.config_warnings().join("; "));
+        }
+        if options.resume && Path::new(config::AUTOSAVE_PATH).exists() {
+            ui_state.status = match resume_from(&mut world, config::AUTOSAVE_PATH) {
+                Ok(()) => {
+                    format!("resumed {} (tick {})", config::AUTOSAVE_PATH, world.tick_count())
+                }
+                Err(err) => format!("resume failed: {err}"),
+            };
+        }
+        let mut autosave = Autosave::new(config::AUTOSAVE_INTERVAL_TICKS, world.tick_count());
+        let truecolor = supports_truecolor();
 
         let mut accumulator = 0.0_f32;
         let mut last_tick = std::time::Instant::now();
         let mut last_render = std::time::Instant::now();
-        let render_interval = Duration::from_secs_f32(1.0 / config::RENDER_HZ);
+        let mut pacer = RenderPacer::new(config::RENDER_ADAPTIVE);
         let mut sim_counter = 0_u32;
         let mut render_counter = 0_u32;
         let mut last_fps_sample = std::time::Instant::now();
         let mut sim_fps = 0.0_f32;
         let mut render_fps = 0.0_f32;
+        let started = std::time::Instant::now();
 
         loop {
             let now = std::time::Instant::now();
@@ -55,53 +118,89 @@ pub fn run() -> Result<(), Box<dyn Error>> {
                 sim_counter += 1;
             }
 
+            // 書き込み失敗で止めず、次の間隔でまた試す
+            if autosave.due(world.tick_count())
+                && let Err(err) = write_autosave(&world, config::AUTOSAVE_PATH)
+            {
+                ui_state.status = format!("autosave failed: {err}");
+            }
+
+            // 書き込めなくなったら以降は出さず、理由をステータス行に出す
+            if let Some(out) = &mut telemetry
+                && out.due(Instant::now())
+                && let Err(err) = out.write(&world.stats())
+            {
+                telemetry = None;
+                ui_state.status = format!("telemetry stopped: {err}");
+            }
+
+            if notices.replace(0) > 0 {
+                bell.record();
+            }
+            if bell.poll(Instant::now(), bell_interval) {
+                let mut out = io::stdout();
+                out.write_all(b"\x07")?;
+                out.flush()?;
+            }
+
             let mut events_processed = 0;
             while events_processed < 100 && event::poll(Duration::from_millis(0))? {
                 events_processed += 1;
-                if let CrosstermEvent::Key(key) = event::read()? {
-                    match key.code {
-                        KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
-                        KeyCode::Up => {
-                            ui_state.mass_total = (ui_state.mass_total + 1.0).min(100.0);
-                        }
-                        KeyCode::Down => {
-                            ui_state.mass_total = (ui_state.mass_total - 1.0).max(1.0);
-                        }
-                        KeyCode::Backspace => {
-                            ui_state.input.pop();
-                        }
-                        KeyCode::Enter => {
-                            let text = ui_state.input.trim().to_string();
-                            if !text.is_empty() {
-                                if text.eq_ignore_ascii_case("sun") {
-                                    world.set_sun(ui_state.camera.pos);
-                                } else {
-                                    world.add_word(text, ui_state.mass_total, ui_state.camera.pos);
-                                }
-                            }
-                            ui_state.input.clear();
-                        }
-                        KeyCode::Char('f') => {
-                            let candidates = build_focus_candidates_from_world(&world);
-                            ui_state.advance_focus(&candidates);
-                        }
-                        KeyCode::Char(ch) => {
-                            if !ch.is_control() && ui_state.input.len() < 32 {
-                                ui_state.input.push(ch);
-                            }
-                        }
-                        _ => {}
-                    }
+                let event = event::read()?;
+                if let CrosstermEvent::Mouse(mouse) = event {
+                    ui_state.handle_mouse(&mut world, mouse);
+                }
+                if let CrosstermEvent::Paste(text) = &event {
+                    ui_state.handle_paste(&mut world, text);
+                }
+                if let CrosstermEvent::Key(key) = event
+                    && !ui_state.handle_key(&mut world, &mut snapshot, key.code)?
+                {
+                    return Ok(());
                 }
             }
 
-            if last_render.elapsed() >= render_interval {
+            if last_render.elapsed() >= pacer.interval() {
                 world.snapshot(&mut snapshot);
-                world.effects_snapshot(&mut effects);
-                let focus_candidates = build_focus_candidates_from_world(&world);
+                let focus_candidates = build_focus_candidates_from_world(
+                    &world,
+                    ui_state.focus_order,
+                    ui_state.proximity_anchor,
+                );
                 ui_state.sync_focus(&focus_candidates);
-                let focus_info = ui_state.update_camera_from_focus(&world, &focus_candidates);
+                let mut focus_info = ui_state.update_camera_from_focus(&world, &focus_candidates);
+                if let Some(report) = ui_state.focus_word_id.and_then(|id| world.collision_zone(id))
+                {
+                    focus_info.push_str(&zone_label(&report));
+                }
+                world.set_gravity_debug_target(ui_state.focus_word_id);
+                ui_state.prediction.clear();
+                if ui_state.show_prediction
+                    && let Some(id) = ui_state.focus_word_id
+                {
+                    let steps = config::PREDICT_STEPS;
+                    world.predict_orbit(id, steps, config::PREDICT_DT, &mut ui_state.prediction);
+                }
+                ui_state.update_merge_animations(&mut world, &snapshot, Instant::now());
+                let effects = world.effects();
                 let stats = world.stats();
+                ui_state.highlight_ids.clear();
+                let blink_on =
+                    (started.elapsed().as_millis() / config::HIGHLIGHT_BLINK_MS).is_multiple_of(2);
+                if blink_on {
+                    for pair in world.closest_pairs() {
+                        ui_state.highlight_ids.push(pair.a);
+                        ui_state.highlight_ids.push(pair.b);
+                    }
+                }
+                ui_state.highlight_ids.extend(ui_state.spring_anchor);
+                ui_state.collect_spring_lines(&world, &snapshot);
+                if ui_state.heatmap {
+                    world.activity_map(&mut ui_state.activity);
+                }
+                ui_state.zone_rings.clear();
+                let zones = world.friction_zones().iter();
+                ui_state.zone_rings.extend(zones.map(|&(center, radius, _)| (center, radius)));
                 if last_fps_sample.elapsed() >= Duration::from_secs(1) {
                     let secs = last_fps_sample.elapsed().as_secs_f32();
                     sim_fps = sim_counter as f32 / secs;
@@ -110,17 +209,70 @@ pub fn run() -> Result<(), Box<dyn Error>> {
                     render_counter = 0;
                     last_fps_sample = std::time::Instant::now();
                 }
-                terminal.draw(|frame| {
-                    let size = frame.size();
-                    let chunks = Layout::default()
-                        .direction(Direction::Vertical)
-                        .constraints([
-                            Constraint::Length(5),
-                            Constraint::Min(3),
-                            Constraint::Length(3),
-                        ])
-                        .split(size);
+                let chunks = Layout::default()
+                    .direction(Direction::Vertical)
+                    .constraints([
+                        Constraint::Length(6),
+                        Constraint::Min(3),
+                        Constraint::Length(3),
+                    ])
+                    .split(terminal.size()?);
 
+                // 構築用バッファへ1フレーム分を描き切ってから表示用と入れ替える。
+                // terminal.draw は表示用を読むだけなので、描画途中の状態は表示されない。
+                ui_state.ensure_viewport(chunks[1].width, chunks[1].height);
+                ui_state.viewport_area = chunks[1];
+                render::draw(
+                    &snapshot,
+                    effects,
+                    ui_state.focus_word_id,
+                    &ui_state.camera,
+                    render::Viewport {
+                        width: chunks[1].width,
+                        height: chunks[1].height,
+                    },
+                    &render::DrawOptions {
+                        highlight_ids: &ui_state.highlight_ids,
+                        density_shading: config::DENSITY_SHADING,
+                        focus_above_effects: config::FOCUS_ABOVE_EFFECTS,
+                        word_wrap: ui_state.word_wrap,
+                        color_mode: config::WORD_COLOR_MODE,
+                        deconflict_shifts: config::DRAW_DECONFLICT_SHIFTS,
+                        prediction: &ui_state.prediction,
+                        gravity_overlay: ui_state.gravity_overlay,
+                        focus_frame: config::FOCUS_FRAME,
+                        layer_order: ui_state.layer_order,
+                        grid_cell_size: if ui_state.grid_overlay {
+                            config::SPATIAL_CELL_SIZE
+                        } else {
+                            0.0
+                        },
+                        grid_counts: config::GRID_SHOW_COUNTS,
+                        lod: config::LOD_ENABLED,
+                        vertical_text: ui_state.vertical_text,
+                        springs: &ui_state.spring_lines,
+                        recency_weight: config::RECENCY_PRIORITY_WEIGHT,
+                        friction_zones: &ui_state.zone_rings,
+                        heatmap: ui_state.heatmap.then_some(&ui_state.activity),
+                        merge_ghosts: &ui_state.merge_ghosts,
+                        merge_streaks: &ui_state.merge_streaks,
+                        merge_hidden: &ui_state.merge_hidden,
+                        color_fn: None,
+                        max_cells_per_word: config::MAX_DRAW_CELLS_PER_WORD,
+                        offscreen_indicators: if ui_state.following_focus() {
+                            0
+                        } else {
+                            config::OFFSCREEN_INDICATOR_MAX
+                        },
+                        offscreen_min_mass: config::OFFSCREEN_INDICATOR_MIN_MASS,
+                    },
+                    &mut ui_state.back_framebuf,
+                );
+                ui_state.swap_framebufs();
+                ui_state.viewport_rows.fill(&ui_state.framebuf, truecolor);
+
+                let draw_started = Instant::now();
+                terminal.draw(|frame| {
                     let debug = stats.gravity_debug;
                     let debug_line = if debug.sample_index >= 0 {
                         format!(
@@ -138,8 +290,8 @@ pub fn run() -> Result<(), Box<dyn Error>> {
                         "grav dbg: none".to_string()
                     };
 
-                    let header = Paragraph::new(format!(
-                        "visible: {} | dust: {} | total: {} | m_vis: {:.1} | m_total: {:.1} | gCand: {:.1} | cCand: {:.1} | sim fps: {:.1} | render fps: {:.1}\n{}\n{}",
+                    let mut header_text = Text::from(format!(
+                        "visible: {} | dust: {} | total: {} | m_vis: {:.1} | m_total: {:.1} | gCand: {:.1} | cCand: {:.1} | sim fps: {:.1} | render fps: {:.1}/{:.0}\n{}\n{}\nspeed 0-{:.1}: {} | queue: {} | t: {:.1}s ({} ticks)",
                         stats.visible_count,
                         stats.dust_count,
                         stats.total_words,
@@ -149,70 +301,76 @@ pub fn run() -> Result<(), Box<dyn Error>> {
                         stats.collision_candidates_avg,
                         sim_fps,
                         render_fps,
+                        1.0 / pacer.interval().as_secs_f32(),
                         debug_line,
-                        focus_info
-                    ))
-                    .block(Block::default().borders(Borders::ALL).title("wordcosmo2"));
+                        focus_info,
+                        stats.speed_histogram.max_speed,
+                        histogram_bars(&stats.speed_histogram.bins),
+                        stats.spawn_queue_len,
+                        stats.elapsed_time,
+                        stats.elapsed_ticks
+                    ));
+                    let drift_style = if mass_drift_exceeded(&stats) {
+                        Style::default().fg(Color::LightRed)
+                    } else {
+                        Style::default()
+                    };
+                    if let Some(line) = header_text.lines.last_mut() {
+                        let drift = format!(" | drift: {:+.4}", stats.mass_drift);
+                        line.spans.push(Span::styled(drift, drift_style));
+                        if config::LOD_ENABLED {
+                            let lod = render::lod_level(snapshot.len(), ui_state.camera.zoom);
+                            line.spans.push(Span::raw(format!(" | lod: {}", lod.label())));
+                        }
+                        let (fx, fx_style) = effect_usage_span(&stats);
+                        line.spans.push(Span::styled(fx, fx_style));
+                        let physics = format!(" | physics: {}", world.physics().name);
+                        line.spans.push(Span::raw(physics));
+                    }
+                    let header = Paragraph::new(header_text)
+                        .block(Block::default().borders(Borders::ALL).title("wordcosmo2"));
                     frame.render_widget(header, chunks[0]);
 
-                    ui_state.ensure_viewport(chunks[1].width, chunks[1].height);
-                    render::draw(
-                        &snapshot,
-                        &effects,
-                        ui_state.focus_word_id,
-                        &ui_state.camera,
-                        render::Viewport {
-                            width: chunks[1].width,
-                            height: chunks[1].height,
-                        },
-                        &mut ui_state.framebuf,
-                    );
-
-                    let framebuf = &ui_state.framebuf;
-                    let width = framebuf.width();
-                    let height = framebuf.height();
-                    let lines: Vec<Line> = (0..height)
-                        .map(|y| {
-                            let mut spans: Vec<Span> = Vec::new();
-                            if width == 0 {
-                                return Line::from(spans);
-                            }
-                            let mut current_text = String::with_capacity(width as usize);
-                            let mut current_color = framebuf.get(0, y).color;
-                            for x in 0..width {
-                                let cell = framebuf.get(x, y);
-                                if cell.color == current_color {
-                                    current_text.push(cell.ch);
-                                } else {
-                                    spans.push(Span::styled(
-                                        mem::take(&mut current_text),
-                                        Style::default().fg(color_for(current_color)),
-                                    ));
-                                    current_text.push(cell.ch);
-                                    current_color = cell.color;
-                                }
-                            }
-                            if !current_text.is_empty() {
-                                spans.push(Span::styled(
-                                    current_text,
-                                    Style::default().fg(color_for(current_color)),
-                                ));
-                            }
-                            Line::from(spans)
-                        })
-                        .collect();
-
-                    let viewport = Paragraph::new(lines)
-                        .block(Block::default().borders(Borders::ALL).title("Viewport"));
+                    let viewport = Block::default().borders(Borders::ALL).title("Viewport");
+                    let inner = viewport.inner(chunks[1]);
                     frame.render_widget(viewport, chunks[1]);
+                    frame.render_widget(&ui_state.viewport_rows, inner);
 
                     let footer = Paragraph::new(format!(
-                        "input: {} | mass_total: {:.1} | ↑↓: mass | Enter: spawn | f: focus next | SUN: create sun | q: quit",
-                        ui_state.input, ui_state.mass_total
+                        "input: {} | mass_total: {:.1} | ↑↓: mass | ←→ PgUp/PgDn: launch ({}) | Enter: spawn | f: focus next | o: order ({}) | p: place ({}) | O: save overview | E: export csv | w: wrap ({}) | v: vertical ({}) | y: orbit ({}) | g: cutoff ({}) | #: grid ({}) | j: heatmap ({}) | l: on top ({}) | k: nearest pairs ({}) | b/B: spring ({}) | i: inert | u: rewind | P: physics ({}) | c/C: copy/load world | [ ]: fx cap ({}) | {{ }}: fx ttl ({:.1}s) | ( ): temp ({:.1}) | drag: grab & throw | SUN: create sun | ZONE: friction zone ({}) /zones: remove | /clear(!): reset (empty) | q: quit",
+                        ui_state.input,
+                        ui_state.mass_total,
+                        ui_state.launch_label(),
+                        ui_state.focus_order.label(),
+                        spawn_mode_label(ui_state.spawn_mode),
+                        if ui_state.word_wrap { "on" } else { "off" },
+                        if ui_state.vertical_text { "on" } else { "off" },
+                        if ui_state.show_prediction { "on" } else { "off" },
+                        if ui_state.gravity_overlay { "on" } else { "off" },
+                        if ui_state.grid_overlay { "on" } else { "off" },
+                        if ui_state.heatmap { "on" } else { "off" },
+                        match ui_state.layer_order {
+                            LayerOrder::EffectsOnTop => "fx",
+                            LayerOrder::WordsOnTop => "words",
+                        },
+                        ui_state.closest_pair_limit,
+                        world.springs().len(),
+                        world.physics().name,
+                        world.effect_capacity(),
+                        world.effect_ttl(),
+                        world.temperature(),
+                        world.friction_zones().len()
                     ))
-                        .block(Block::default().borders(Borders::ALL).title("Controls"));
+                        .block(Block::default().borders(Borders::ALL).title(
+                            if ui_state.status.is_empty() {
+                                "Controls".to_string()
+                            } else {
+                                format!("Controls - {}", ui_state.status)
+                            },
+                        ));
                     frame.render_widget(footer, chunks[2]);
                 })?;
+                pacer.record(draw_started.elapsed());
 
                 last_render = std::time::Instant::now();
                 render_counter += 1;
@@ -230,20 +388,69 @@ fn shutdown_terminal(
     terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
 ) -> Result<(), Box<dyn Error>> {
     disable_raw_mode()?;
-    execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+    execute!(
+        terminal.backend_mut(),
+        LeaveAlternateScreen,
+        DisableMouseCapture,
+        DisableBracketedPaste
+    )?;
     terminal.show_cursor()?;
     Ok(())
 }
 
 struct UiState {
     camera: render::Camera,
+    // 表示用（terminal.draw が読む）と構築用（render::draw が書く）
     framebuf: render::FrameBuffer,
+    back_framebuf: render::FrameBuffer,
     input: String,
     mass_total: f32,
     focus_component: Option<String>,
     focus_word_id: Option<WordId>,
     focus_index: usize,
     focus_total: usize,
+    closest_pair_limit: usize,
+    highlight_ids: Vec<WordId>,
+    focus_order: FocusOrder,
+    proximity_anchor: Vec2,
+    word_wrap: bool,
+    // 'v' で切り替える縦書き
+    vertical_text: bool,
+    // 'y' で切り替えるフォーカス語の予測軌道
+    show_prediction: bool,
+    prediction: Vec<Vec2>,
+    // 'b' でばねの一端に選んだ語。もう一度 'b' でフォーカス語と結ぶ
+    spring_anchor: Option<WordId>,
+    spring_lines: Vec<(Vec2, Vec2)>,
+    zone_rings: Vec<(Vec2, f32)>,
+    // 再生中の合体アニメーション（記録, 開始時刻）と、このフレームの幻影・軌跡・隠す合体語
+    merge_anims: Vec<(MergeRecord, Instant)>,
+    merge_records: Vec<MergeRecord>,
+    merge_ghosts: Vec<WordSnapshot>,
+    merge_streaks: Vec<(Vec2, Vec2)>,
+    merge_hidden: Vec<WordId>,
+    // 直近フレームの Viewport 枠の位置。マウス座標をワールド座標へ戻すのに使う
+    viewport_area: Rect,
+    // 'g' で切り替える重力カットオフ範囲の表示
+    gravity_overlay: bool,
+    // '#' で切り替える空間ハッシュのセル境界の表示
+    grid_overlay: bool,
+    // 'j' で切り替える合体・分裂のヒートマップ
+    heatmap: bool,
+    activity: ActivityMap,
+    // 'l' で切り替えるエフェクトと語の前後関係
+    layer_order: LayerOrder,
+    // 'P' で循環する config::PHYSICS_PRESETS の位置
+    physics_preset: usize,
+    spawn_mode: SpawnMode,
+    // 投入時の初速。角度は画面上で右が 0°・反時計回り、速さ 0 ならランダムな初速
+    launch_angle_deg: f32,
+    launch_speed: f32,
+    // 保存結果などの一行メッセージ
+    status: String,
+    viewport_rows: ViewportRows,
+    // 'c'/'C' で初めて使うときに開く
+    clipboard: Option<arboard::Clipboard>,
 }
 
 impl UiState {
@@ -251,21 +458,505 @@ impl UiState {
         Self {
             camera: render::Camera::default(),
             framebuf: render::FrameBuffer::new(0, 0),
+            back_framebuf: render::FrameBuffer::new(0, 0),
             input: String::new(),
             mass_total: 10.0,
             focus_component: None,
             focus_word_id: None,
             focus_index: 0,
             focus_total: 0,
+            closest_pair_limit: 0,
+            highlight_ids: Vec::new(),
+            focus_order: FocusOrder::ByMass,
+            proximity_anchor: Vec2::ZERO,
+            word_wrap: false,
+            vertical_text: config::VERTICAL_TEXT,
+            show_prediction: false,
+            prediction: Vec::new(),
+            spring_anchor: None,
+            spring_lines: Vec::new(),
+            zone_rings: Vec::new(),
+            merge_anims: Vec::new(),
+            merge_records: Vec::new(),
+            merge_ghosts: Vec::new(),
+            merge_streaks: Vec::new(),
+            merge_hidden: Vec::new(),
+            viewport_area: Rect::default(),
+            gravity_overlay: false,
+            grid_overlay: false,
+            heatmap: false,
+            activity: ActivityMap::default(),
+            layer_order: config::LAYER_ORDER,
+            physics_preset: 0,
+            spawn_mode: config::SPAWN_MODE,
+            launch_angle_deg: 0.0,
+            launch_speed: 0.0,
+            status: String::new(),
+            viewport_rows: ViewportRows::default(),
+            clipboard: None,
+        }
+    }
+
+    // 近傍順の基準点は切替時のカメラ位置に固定する。
+    // フォーカス追従で動くカメラを基準にすると、隣接2語の間を往復するだけになるため。
+    fn toggle_focus_order(&mut self) {
+        self.focus_order = match self.focus_order {
+            FocusOrder::ByMass => FocusOrder::ByProximity,
+            FocusOrder::ByProximity => FocusOrder::ByMass,
+        };
+        self.proximity_anchor = self.camera.pos;
+    }
+
+    // 1回目でフォーカス語を一端に選び、フォーカスを移してからの2回目で結ぶ。
+    // 同じ語で押すと選択を取り消す
+    fn toggle_spring(&mut self, world: &mut World) {
+        let Some(focus) = self.focus_word_id else {
+            self.status = "spring: focus a word first".to_string();
+            return;
+        };
+        match self.spring_anchor.take() {
+            None => {
+                self.spring_anchor = Some(focus);
+                self.status = format!("spring: id={} selected, focus another and press b", focus);
+            }
+            Some(anchor) if anchor == focus => {
+                self.status = "spring: cancelled".to_string();
+            }
+            Some(anchor) => {
+                self.status = if world.add_spring(anchor, focus, None, config::SPRING_K) {
+                    format!("spring: id={} - id={}", anchor, focus)
+                } else {
+                    format!("spring: id={} is gone", anchor)
+                };
+            }
         }
     }
 
+    // 状態文字列なら復元し、それ以外は入力欄へ足す
+    fn handle_paste(&mut self, world: &mut World, text: &str) {
+        if text.trim_start().starts_with(share::SHARE_PREFIX) {
+            self.restore_shared(world, text);
+            return;
+        }
+        for ch in text.chars().filter(|ch| !ch.is_control()) {
+            if self.input.len() >= 32 {
+                break;
+            }
+            self.input.push(ch);
+        }
+    }
+
+    fn restore_shared(&mut self, world: &mut World, text: &str) {
+        self.status = match share::restore_share_text(world, text) {
+            Ok(()) => {
+                self.focus_component = None;
+                self.focus_word_id = None;
+                self.spring_anchor = None;
+                format!("restored {} words", world.stats().total_words)
+            }
+            Err(err) => format!("restore failed: {err}"),
+        };
+    }
+
+    // 一度作った Clipboard を使い回す。X11 では持ち主が生きている間だけ中身が残る
+    fn clipboard(&mut self) -> Result<&mut arboard::Clipboard, arboard::Error> {
+        if self.clipboard.is_none() {
+            self.clipboard = Some(arboard::Clipboard::new()?);
+        }
+        Ok(self.clipboard.as_mut().unwrap())
+    }
+
+    // クリップボードが使えない環境では SHARE_PATH に書く。失敗はステータス行に出す
+    fn copy_shared(&mut self, world: &World) {
+        let text = share::share_text(world);
+        let copied = self.clipboard().and_then(|clipboard| clipboard.set_text(text.as_str()));
+        self.status = match copied {
+            Ok(()) => "copied to clipboard".to_string(),
+            Err(clip_err) => match std::fs::write(config::SHARE_PATH, &text) {
+                Ok(()) => format!("saved {} (clipboard: {clip_err})", config::SHARE_PATH),
+                Err(err) => format!("copy failed: {err}"),
+            },
+        };
+    }
+
+    // クリップボードに状態文字列があればそれを、なければ SHARE_PATH を読む
+    fn load_shared(&mut self, world: &mut World) {
+        let pasted = self
+            .clipboard()
+            .and_then(|clipboard| clipboard.get_text())
+            .ok()
+            .filter(|text| text.trim_start().starts_with(share::SHARE_PREFIX));
+        let loaded = match pasted {
+            Some(text) => Ok(text),
+            None => std::fs::read_to_string(config::SHARE_PATH),
+        };
+        match loaded {
+            Ok(text) => self.restore_shared(world, &text),
+            Err(err) => self.status = format!("load failed: {err}"),
+        }
+    }
+
+    fn toggle_inert(&mut self, world: &mut World) {
+        let Some(focus) = self.focus_word_id else {
+            self.status = "inert: focus a word first".to_string();
+            return;
+        };
+        let inert = !world.is_inert(focus);
+        self.status = if world.set_inert(focus, inert) {
+            format!("inert: id={} {}", focus, if inert { "on" } else { "off" })
+        } else {
+            format!("inert: id={} is gone", focus)
+        };
+    }
+
+    // キー入力を1つ処理する。終了するキーなら false
+    fn handle_key(
+        &mut self,
+        world: &mut World,
+        snapshot: &mut Vec<WordSnapshot>,
+        code: KeyCode,
+    ) -> io::Result<bool> {
+        match code {
+            KeyCode::Char('q') | KeyCode::Esc => return Ok(false),
+            KeyCode::Up => {
+                self.mass_total = (self.mass_total + 1.0).min(100.0);
+            }
+            KeyCode::Down => {
+                self.mass_total = (self.mass_total - 1.0).max(1.0);
+            }
+            KeyCode::Left => self.rotate_launch(config::LAUNCH_ANGLE_STEP_DEG),
+            KeyCode::Right => self.rotate_launch(-config::LAUNCH_ANGLE_STEP_DEG),
+            KeyCode::PageUp => self.adjust_launch_speed(config::LAUNCH_SPEED_STEP),
+            KeyCode::PageDown => {
+                self.adjust_launch_speed(-config::LAUNCH_SPEED_STEP);
+            }
+            KeyCode::Backspace => {
+                self.input.pop();
+            }
+            KeyCode::Enter => {
+                let text = self.input.trim().to_string();
+                if !text.is_empty() {
+                    if text.eq_ignore_ascii_case("sun") {
+                        world.set_sun(self.camera.pos);
+                    } else if text.eq_ignore_ascii_case("zone") {
+                        world.add_friction_zone(
+                            self.camera.pos,
+                            config::FRICTION_ZONE_RADIUS,
+                            config::FRICTION_ZONE_DAMP,
+                        );
+                    } else if text == "/zones" {
+                        world.clear_friction_zones();
+                    } else if text == "/clear" || text == "/clear!" {
+                        // "/clear!" は初期語を撒かずに空のままにする
+                        world.clear();
+                        if text == "/clear" {
+                            world.respawn_initial_words();
+                        }
+                        self.focus_component = None;
+                        self.focus_word_id = None;
+                    } else if let Some(vel) = self.launch_velocity() {
+                        world.add_word_with_velocity(text, self.mass_total, self.camera.pos, vel);
+                    } else {
+                        world.add_word_with_mode(
+                            text,
+                            self.mass_total,
+                            self.camera.pos,
+                            self.view_half_extent(),
+                            self.spawn_mode,
+                        );
+                    }
+                }
+                self.input.clear();
+            }
+            KeyCode::Char('f') => {
+                let candidates = build_focus_candidates_from_world(
+                    world,
+                    self.focus_order,
+                    self.proximity_anchor,
+                );
+                self.advance_focus(&candidates);
+            }
+            KeyCode::Char('b') if self.input.is_empty() => {
+                self.toggle_spring(world);
+            }
+            KeyCode::Char('B') if self.input.is_empty() => {
+                world.clear_springs();
+                self.spring_anchor = None;
+                self.status = "springs cleared".to_string();
+            }
+            KeyCode::Char('c') if self.input.is_empty() => {
+                self.copy_shared(world);
+            }
+            KeyCode::Char('C') if self.input.is_empty() => {
+                self.load_shared(world);
+            }
+            KeyCode::Char('i') if self.input.is_empty() => {
+                self.toggle_inert(world);
+            }
+            KeyCode::Char('u') if self.input.is_empty() => {
+                self.rewind(world);
+            }
+            KeyCode::Char('P') if self.input.is_empty() => {
+                self.cycle_physics(world);
+            }
+            KeyCode::Char('o') if self.input.is_empty() => {
+                self.toggle_focus_order();
+            }
+            KeyCode::Char('O') if self.input.is_empty() => {
+                world.snapshot(snapshot);
+                let overview = render::render_world_overview(
+                    snapshot,
+                    config::OVERVIEW_WIDTH,
+                    config::OVERVIEW_HEIGHT,
+                );
+                self.status =
+                    match std::fs::write(config::OVERVIEW_PATH, overview.to_text()) {
+                        Ok(()) => format!("saved {}", config::OVERVIEW_PATH),
+                        Err(err) => format!("save failed: {err}"),
+                    };
+            }
+            KeyCode::Char('E') if self.input.is_empty() => {
+                let csv = world.export_csv_with(config::CSV_INCLUDE_DUST);
+                self.status = match std::fs::write(config::CSV_EXPORT_PATH, csv) {
+                    Ok(()) => format!("saved {}", config::CSV_EXPORT_PATH),
+                    Err(err) => format!("save failed: {err}"),
+                };
+            }
+            // 文字のキーは入力が空のときだけ操作に使い、入力中はそのまま語の文字にする
+            KeyCode::Char('p') if self.input.is_empty() => {
+                self.cycle_spawn_mode();
+            }
+            KeyCode::Char('w') if self.input.is_empty() => {
+                self.word_wrap = !self.word_wrap;
+            }
+            KeyCode::Char('v') if self.input.is_empty() => {
+                self.vertical_text = !self.vertical_text;
+            }
+            KeyCode::Char('y') if self.input.is_empty() => {
+                self.show_prediction = !self.show_prediction;
+            }
+            KeyCode::Char('g') if self.input.is_empty() => {
+                self.gravity_overlay = !self.gravity_overlay;
+            }
+            KeyCode::Char('j') if self.input.is_empty() => {
+                self.heatmap = !self.heatmap;
+            }
+            KeyCode::Char('#') if self.input.is_empty() => {
+                self.grid_overlay = !self.grid_overlay;
+            }
+            KeyCode::Char('l') if self.input.is_empty() => {
+                self.layer_order = match self.layer_order {
+                    LayerOrder::EffectsOnTop => LayerOrder::WordsOnTop,
+                    LayerOrder::WordsOnTop => LayerOrder::EffectsOnTop,
+                };
+            }
+            KeyCode::Char('k') if self.input.is_empty() => {
+                self.closest_pair_limit = match self.closest_pair_limit {
+                    0 => 1,
+                    1 => config::CLOSEST_PAIRS_TOP_K,
+                    _ => 0,
+                };
+                world.set_closest_pair_limit(self.closest_pair_limit);
+            }
+            KeyCode::Char('[') if self.input.is_empty() => {
+                let capacity = world
+                    .effect_capacity()
+                    .saturating_sub(config::EFFECT_CAPACITY_STEP);
+                world.set_effect_capacity(capacity);
+            }
+            KeyCode::Char(']') if self.input.is_empty() => {
+                let capacity = (world.effect_capacity() + config::EFFECT_CAPACITY_STEP)
+                    .min(config::EFFECT_CAPACITY_MAX);
+                world.set_effect_capacity(capacity);
+            }
+            KeyCode::Char('{') if self.input.is_empty() => {
+                world.set_effect_ttl(world.effect_ttl() - config::EFFECT_TTL_STEP);
+            }
+            KeyCode::Char('}') if self.input.is_empty() => {
+                let ttl = (world.effect_ttl() + config::EFFECT_TTL_STEP)
+                    .min(config::EFFECT_TTL_MAX);
+                world.set_effect_ttl(ttl);
+            }
+            KeyCode::Char('(') if self.input.is_empty() => {
+                let step = config::THERMAL_TEMPERATURE_STEP;
+                world.set_temperature(world.temperature() - step);
+            }
+            KeyCode::Char(')') if self.input.is_empty() => {
+                let temperature = (world.temperature()
+                    + config::THERMAL_TEMPERATURE_STEP)
+                    .min(config::THERMAL_TEMPERATURE_MAX);
+                world.set_temperature(temperature);
+            }
+            KeyCode::Char(ch) if !ch.is_control() && self.input.len() < 32 => {
+                self.input.push(ch);
+            }
+            _ => {}
+        }
+        Ok(true)
+    }
+
+    // 直近の写しへ巻き戻す。再生中の合体アニメーションは別の時間のものなので捨てる
+    fn rewind(&mut self, world: &mut World) {
+        self.status = match world.rewind() {
+            Some(tick) => {
+                self.merge_anims.clear();
+                format!("rewind: tick {} ({} left)", tick, world.checkpoint_count())
+            }
+            None => "rewind: no checkpoint yet".to_string(),
+        };
+    }
+
+    // 次のプリセットへ切り替える。語・質量・速度はそのままで、挙動のパラメータだけ変わる
+    fn cycle_physics(&mut self, world: &mut World) {
+        self.physics_preset = (self.physics_preset + 1) % config::PHYSICS_PRESETS.len();
+        let preset = config::PHYSICS_PRESETS[self.physics_preset];
+        world.set_physics(preset);
+        self.status = format!("physics: {}", preset.name);
+    }
+
+    // 端末のセル座標を、枠の内側を原点とする描画座標を経てワールド座標へ戻す。枠の外なら None
+    fn mouse_to_world(&self, column: u16, row: u16) -> Option<Vec2> {
+        let area = self.viewport_area;
+        let inside = column > area.x
+            && row > area.y
+            && column + 1 < area.x + area.width
+            && row + 1 < area.y + area.height;
+        if !inside {
+            return None;
+        }
+        let x = (column - area.x - 1) as f32 + 0.5;
+        let y = (row - area.y - 1) as f32 + 0.5;
+        let viewport = render::Viewport {
+            width: area.width,
+            height: area.height,
+        };
+        Some(self.camera.screen_to_world(x, y, viewport))
+    }
+
+    // 左ボタンで掴み、ドラッグで動かし、離すとその速度で投げる
+    fn handle_mouse(&mut self, world: &mut World, mouse: MouseEvent) {
+        let pos = self.mouse_to_world(mouse.column, mouse.row);
+        match mouse.kind {
+            MouseEventKind::Down(MouseButton::Left) => {
+                let Some(pos) = pos else {
+                    return;
+                };
+                let reach = config::DRAG_PICK_CELLS / self.camera.zoom.max(f32::EPSILON);
+                if let Some(id) = world.grab_nearest(pos, reach) {
+                    self.status = format!("grab: id={}", id);
+                }
+            }
+            MouseEventKind::Drag(MouseButton::Left) => {
+                if let Some(pos) = pos {
+                    world.drag_to(pos);
+                }
+            }
+            MouseEventKind::Up(MouseButton::Left) => {
+                if let Some((id, vel)) = world.release_grab() {
+                    self.status = format!("throw: id={} v=({:.1}, {:.1})", id, vel.x, vel.y);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn collect_spring_lines(&mut self, world: &World, snapshot: &[WordSnapshot]) {
+        self.spring_lines.clear();
+        let pos_of = |id: WordId| snapshot.iter().find(|w| w.id == id).map(|w| w.pos);
+        for &(a, b, _, _) in world.springs() {
+            if let (Some(pa), Some(pb)) = (pos_of(a), pos_of(b)) {
+                self.spring_lines.push((pa, pb));
+            }
+        }
+    }
+
+    // 新しい合体の記録を now から再生し始め、MERGE_ANIM_SECS を過ぎたものを捨てる。
+    // 合体語が見えなくなっていれば（続けて合体したなど）合体した位置へ寄せる
+    fn update_merge_animations(
+        &mut self,
+        world: &mut World,
+        snapshot: &[WordSnapshot],
+        now: Instant,
+    ) {
+        world.drain_merge_records(&mut self.merge_records);
+        self.merge_anims.extend(self.merge_records.drain(..).map(|record| (record, now)));
+        let duration = config::MERGE_ANIM_SECS;
+        let progress = |started: Instant| now.saturating_duration_since(started).as_secs_f32();
+        self.merge_anims.retain(|&(_, started)| progress(started) < duration);
+        self.merge_ghosts.clear();
+        self.merge_streaks.clear();
+        self.merge_hidden.clear();
+        for (record, started) in &self.merge_anims {
+            let target = snapshot
+                .iter()
+                .find(|w| w.id == record.merged)
+                .map_or(record.pos, |w| w.pos);
+            let t = progress(*started) / duration;
+            let (ghosts, streaks) = (&mut self.merge_ghosts, &mut self.merge_streaks);
+            render::merge_ghosts(record, target, t, ghosts, streaks);
+            self.merge_hidden.push(record.merged);
+        }
+    }
+
+    fn cycle_spawn_mode(&mut self) {
+        self.spawn_mode = match self.spawn_mode {
+            SpawnMode::AtPoint => SpawnMode::RandomInView,
+            SpawnMode::RandomInView => SpawnMode::RandomInWorld,
+            SpawnMode::RandomInWorld => SpawnMode::AtPoint,
+        };
+    }
+
+    fn rotate_launch(&mut self, delta_deg: f32) {
+        self.launch_angle_deg = (self.launch_angle_deg + delta_deg).rem_euclid(360.0);
+    }
+
+    fn adjust_launch_speed(&mut self, delta: f32) {
+        self.launch_speed = (self.launch_speed + delta).clamp(0.0, config::LAUNCH_SPEED_MAX);
+    }
+
+    // 画面の y は下向きなので、反時計回りの角度にするため y 成分の符号を反転する
+    fn launch_velocity(&self) -> Option<Vec2> {
+        if self.launch_speed <= 0.0 {
+            return None;
+        }
+        let angle = self.launch_angle_deg.to_radians();
+        Some(Vec2::new(angle.cos(), -angle.sin()) * self.launch_speed)
+    }
+
+    fn launch_label(&self) -> String {
+        if self.launch_speed <= 0.0 {
+            "random".to_string()
+        } else {
+            format!("{:.0}° {:.1}", self.launch_angle_deg, self.launch_speed)
+        }
+    }
+
+    // 現在のビューポートがワールド座標で覆う半幅・半高
+    fn view_half_extent(&self) -> Vec2 {
+        let viewport = render::Viewport {
+            width: self.framebuf.width(),
+            height: self.framebuf.height(),
+        };
+        self.camera.pos - self.camera.screen_to_world(0.0, 0.0, viewport)
+    }
+
     fn ensure_viewport(&mut self, width: u16, height: u16) {
-        if self.framebuf.width() != width || self.framebuf.height() != height {
-            self.framebuf.resize(width, height);
+        for buf in [&mut self.framebuf, &mut self.back_framebuf] {
+            if buf.width() != width || buf.height() != height {
+                buf.resize(width, height);
+            }
         }
     }
 
+    fn swap_framebufs(&mut self) {
+        mem::swap(&mut self.framebuf, &mut self.back_framebuf);
+    }
+
+    // カメラがフォーカス語を追っている間は、画面外の矢印を出さない
+    fn following_focus(&self) -> bool {
+        self.focus_component.is_some()
+    }
+
     fn advance_focus(&mut self, candidates: &[FocusCandidate]) {
         if candidates.is_empty() {
             self.focus_component = None;
@@ -296,16 +987,15 @@ impl UiState {
             return;
         }
         self.focus_total = candidates.len();
-        if let Some(component) = self.focus_component.as_deref() {
-            if let Some((idx, candidate)) = candidates
+        if let Some(component) = self.focus_component.as_deref()
+            && let Some((idx, candidate)) = candidates
                 .iter()
                 .enumerate()
                 .find(|(_, c)| c.component == component)
-            {
-                self.focus_index = idx + 1;
-                self.focus_word_id = Some(candidate.word_id);
-                return;
-            }
+        {
+            self.focus_index = idx + 1;
+            self.focus_word_id = Some(candidate.word_id);
+            return;
         }
         self.focus_component = None;
         self.focus_word_id = None;
@@ -318,7 +1008,7 @@ impl UiState {
         candidates: &[FocusCandidate],
     ) -> String {
         let Some(component) = self.focus_component.as_deref() else {
-            return format!("focus: none");
+            return "focus: none".to_string();
         };
         let candidate = candidates.iter().find(|c| c.component == component);
         let Some(candidate) = candidate else {
@@ -326,70 +1016,401 @@ impl UiState {
             self.focus_word_id = None;
             self.focus_index = 0;
             self.focus_total = 0;
-            return format!("focus: none");
+            return "focus: none".to_string();
         };
         self.focus_word_id = Some(candidate.word_id);
         let Some(word) = world.words.iter().find(|w| w.id == candidate.word_id) else {
             self.focus_word_id = None;
-            return format!("focus: none");
+            return "focus: none".to_string();
         };
         let target = word.pos;
         self.camera.pos = lerp_vec2(self.camera.pos, target, 0.2);
         let text = display_text(&word.text);
         format!(
-            "focus: {}/{} | key={} | id={} | mass={:.2} | text={} ",
+            "focus: {}/{} | key={} | id={} | mass={:.2} | merges={} splits={} | text={} ",
             self.focus_index,
             self.focus_total,
             component,
             word.id,
             word.mass_visible,
+            word.merge_count,
+            word.split_count,
             text
         )
     }
 }
 
+// 描画間隔。terminal.draw の所要時間が間隔に対して重ければ RENDER_HZ_MIN まで延ばし、
+// 軽くなれば RENDER_HZ まで戻す。adaptive でなければ常に RENDER_HZ
+struct RenderPacer {
+    adaptive: bool,
+    interval: Duration,
+    draw_secs: f32,
+}
+
+impl RenderPacer {
+    fn new(adaptive: bool) -> Self {
+        Self {
+            adaptive,
+            interval: Self::fastest(),
+            draw_secs: 0.0,
+        }
+    }
+
+    fn fastest() -> Duration {
+        Duration::from_secs_f32(1.0 / config::RENDER_HZ)
+    }
+
+    fn slowest() -> Duration {
+        Duration::from_secs_f32(1.0 / config::RENDER_HZ_MIN.min(config::RENDER_HZ))
+    }
+
+    fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    fn record(&mut self, draw_time: Duration) {
+        if !self.adaptive {
+            return;
+        }
+        let alpha = config::RENDER_DRAW_SMOOTHING.clamp(0.0, 1.0);
+        self.draw_secs += (draw_time.as_secs_f32() - self.draw_secs) * alpha;
+        let busy = self.interval.as_secs_f32() * config::RENDER_BUSY_RATIO;
+        if self.draw_secs > busy {
+            self.interval = self.interval.mul_f32(1.25).min(Self::slowest());
+        } else if self.draw_secs < busy * 0.5 {
+            self.interval = self.interval.mul_f32(0.9).max(Self::fastest());
+        }
+    }
+}
+
+// 直近の鳴動から interval 以内に来た通知はまとめ、間隔が空いたときに1回だけ鳴らす
+#[derive(Debug, Default)]
+struct BellThrottle {
+    pending: bool,
+    last_ring: Option<Instant>,
+}
+
+impl BellThrottle {
+    fn record(&mut self) {
+        self.pending = true;
+    }
+
+    fn poll(&mut self, now: Instant, interval: Duration) -> bool {
+        if !self.pending {
+            return false;
+        }
+        if self
+            .last_ring
+            .is_some_and(|last| now.saturating_duration_since(last) < interval)
+        {
+            return false;
+        }
+        self.pending = false;
+        self.last_ring = Some(now);
+        true
+    }
+}
+
+// WorldStats の要約を interval ごとに NDJSON（1行1オブジェクト）で書く
+struct Telemetry {
+    out: Box<dyn Write>,
+    interval: Duration,
+    last: Option<Instant>,
+}
+
+impl Telemetry {
+    fn new(out: Box<dyn Write>, interval: Duration) -> Self {
+        Self {
+            out,
+            interval,
+            last: None,
+        }
+    }
+
+    // 初回は即時、以降は前回から interval 経過ごと
+    fn due(&mut self, now: Instant) -> bool {
+        if self
+            .last
+            .is_some_and(|last| now.saturating_duration_since(last) < self.interval)
+        {
+            return false;
+        }
+        self.last = Some(now);
+        true
+    }
+
+    fn write(&mut self, stats: &WorldStats) -> io::Result<()> {
+        writeln!(self.out, "{}", telemetry_json(stats))?;
+        self.out.flush()
+    }
+}
+
+// tick 基準の間隔。起動（復元）時点から interval_ticks 進むごと
+struct Autosave {
+    interval_ticks: u64,
+    last_tick: u64,
+}
+
+impl Autosave {
+    fn new(interval_ticks: u64, tick: u64) -> Self {
+        Self {
+            interval_ticks,
+            last_tick: tick,
+        }
+    }
+
+    fn due(&mut self, tick: u64) -> bool {
+        // clear や巻き戻しで tick が戻ったら、そこから数え直す
+        self.last_tick = self.last_tick.min(tick);
+        if self.interval_ticks == 0 || tick < self.last_tick + self.interval_ticks {
+            return false;
+        }
+        self.last_tick = tick;
+        true
+    }
+}
+
+// 書きかけのファイルが残らないよう、一時ファイルに書いてから差し替える
+fn write_autosave(world: &World, path: &str) -> io::Result<()> {
+    let tmp = format!("{path}.tmp");
+    std::fs::write(&tmp, world.save_json().to_string())?;
+    std::fs::rename(&tmp, path)
+}
+
+fn resume_from(world: &mut World, path: &str) -> Result<(), Box<dyn Error>> {
+    let value: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(path)?)?;
+    world.restore_json(&value)?;
+    Ok(())
+}
+
+fn telemetry_json(stats: &WorldStats) -> serde_json::Value {
+    serde_json::json!({
+        "tick": stats.tick,
+        "elapsed_ticks": stats.elapsed_ticks,
+        "elapsed_time": stats.elapsed_time,
+        "visible_words": stats.visible_count,
+        "total_words": stats.total_words,
+        "total_mass": stats.total_mass,
+        "total_mass_visible": stats.total_mass_visible,
+        "kinetic_energy": stats.kinetic_energy,
+        "merges": stats.merges,
+        "splits": stats.splits,
+    })
+}
+
+// 最近接語との実測値をしきい値と並べ、合体・分裂が起きる理由を読めるようにする
+fn zone_label(report: &core::ZoneReport) -> String {
+    let zone = match report.zone {
+        core::CollisionZone::Merge => "合体圏",
+        core::CollisionZone::Split => "分裂圏",
+        core::CollisionZone::BounceOnly => "反発のみ",
+    };
+    format!(
+        "| near id={} v_rel {:.2} (merge <{:.2} split >={:.2}) ratio {:.2} (tidal >={:.2}) {}",
+        report.neighbor,
+        report.rel_speed,
+        report.merge_speed_max,
+        report.split_speed_min,
+        report.mass_ratio,
+        report.tidal_mass_ratio,
+        zone
+    )
+}
+
+// エフェクトの埋まり具合。直近 tick に上書きが出ていれば目立たせる
+fn effect_usage_span(stats: &WorldStats) -> (String, Style) {
+    let text = format!(
+        " | fx: {:.0}% +{} (overflow {})",
+        stats.effect_usage * 100.0,
+        stats.effect_spawns,
+        stats.effect_overflows
+    );
+    let style = if stats.effect_overflows > 0 {
+        Style::default().fg(Color::LightRed)
+    } else {
+        Style::default()
+    };
+    (text, style)
+}
+
+// 総質量が投入量から MASS_DRIFT_WARN_RATIO を超えてずれたか（保存則の破れの目安）
+fn mass_drift_exceeded(stats: &WorldStats) -> bool {
+    stats.mass_drift.abs() > stats.mass_budget.abs().max(1.0) * config::MASS_DRIFT_WARN_RATIO
+}
+
 fn lerp_vec2(a: Vec2, b: Vec2, alpha: f32) -> Vec2 {
     a + (b - a) * alpha
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum FocusOrder {
+    ByMass,
+    ByProximity,
+}
+
+impl FocusOrder {
+    fn label(self) -> &'static str {
+        match self {
+            FocusOrder::ByMass => "mass",
+            FocusOrder::ByProximity => "near",
+        }
+    }
+}
+
+// 表示用フレームを同色ランごとの Span に分けた行として保持する。
+// 行・Span の Vec と各 Span の String はフレーム間で clear して使い回す。
+#[derive(Default)]
+struct ViewportRows {
+    lines: Vec<Line<'static>>,
+}
+
+impl ViewportRows {
+    fn fill(&mut self, framebuf: &render::FrameBuffer, truecolor: bool) {
+        let width = framebuf.width();
+        let height = framebuf.height() as usize;
+        self.lines.resize_with(height, Line::default);
+        for (y, line) in self.lines.iter_mut().enumerate() {
+            let spans = &mut line.spans;
+            let mut used = 0;
+            if width > 0 {
+                let y = y as u16;
+                let mut current_color = framebuf.get(0, y).color;
+                let mut text = next_span(spans, &mut used, color_style(current_color, truecolor));
+                let mut prev_wide = false;
+                for x in 0..width {
+                    let cell = framebuf.get(x, y);
+                    // 全角文字の右半分は出力しない。左半分が上書きされていれば空白で埋める。
+                    let ch = if cell.ch == render::WIDE_CONTINUATION {
+                        if prev_wide {
+                            prev_wide = false;
+                            continue;
+                        }
+                        ' '
+                    } else {
+                        cell.ch
+                    };
+                    prev_wide = char_cell_width(ch) == 2;
+                    if cell.color != current_color {
+                        current_color = cell.color;
+                        let style = color_style(current_color, truecolor);
+                        text = next_span(spans, &mut used, style);
+                    }
+                    text.push(ch);
+                    if ch == cell.ch {
+                        text.extend(cell.visible_mark());
+                    }
+                }
+            }
+            spans.truncate(used);
+        }
+    }
+}
+
+// 枠の内側へ行をそのまま書く。Paragraph と違って行を受け取り直さないので使い回せる
+impl ratatui::widgets::Widget for &ViewportRows {
+    fn render(self, area: Rect, buf: &mut ratatui::buffer::Buffer) {
+        for (row, line) in self.lines.iter().take(area.height as usize).enumerate() {
+            buf.set_line(area.x, area.y + row as u16, line, area.width);
+        }
+    }
+}
+
+// spans[*used] を style にして中身を空にし、そこへ書く String を返す。足りなければ足す
+fn next_span<'s>(
+    spans: &'s mut Vec<Span<'static>>,
+    used: &mut usize,
+    style: Style,
+) -> &'s mut String {
+    if *used == spans.len() {
+        spans.push(Span::default());
+    }
+    let span = &mut spans[*used];
+    *used += 1;
+    span.style = style;
+    let text = span.content.to_mut();
+    text.clear();
+    text
+}
+
+fn color_style(color: ColorId, truecolor: bool) -> Style {
+    Style::default().fg(color_for(color, truecolor))
+}
+
+const HISTOGRAM_GLYPHS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+// 最多ビンを満杯の高さとして、各ビンを1セルのブロック文字で表す。0 件のビンは空白。
+fn histogram_bars(bins: &[usize]) -> String {
+    let peak = bins.iter().copied().max().unwrap_or(0);
+    bins.iter()
+        .map(|&count| {
+            if count == 0 || peak == 0 {
+                return ' ';
+            }
+            let level = (count * HISTOGRAM_GLYPHS.len()).div_ceil(peak);
+            HISTOGRAM_GLYPHS[level.clamp(1, HISTOGRAM_GLYPHS.len()) - 1]
+        })
+        .collect()
+}
+
+fn spawn_mode_label(mode: SpawnMode) -> &'static str {
+    match mode {
+        SpawnMode::AtPoint => "point",
+        SpawnMode::RandomInView => "view",
+        SpawnMode::RandomInWorld => "world",
+    }
+}
+
 #[derive(Clone, Debug)]
 struct FocusCandidate {
     component: String,
     word_id: WordId,
     mass_visible: f32,
+    distance: f32,
 }
 
-fn build_focus_candidates_from_world(world: &World) -> Vec<FocusCandidate> {
-    let mut map: HashMap<String, (WordId, f32)> = HashMap::new();
+fn build_focus_candidates_from_world(
+    world: &World,
+    order: FocusOrder,
+    anchor: Vec2,
+) -> Vec<FocusCandidate> {
+    let mut map: HashMap<String, (WordId, f32, Vec2)> = HashMap::new();
     for word in &world.words {
         if word.mass_visible < config::MIN_VISIBLE_MASS {
             continue;
         }
         let components = split_components(&word.text);
         for component in components {
-            let entry = map.entry(component).or_insert((word.id, word.mass_visible));
-            let (best_id, best_mass) = entry;
+            let entry = map
+                .entry(component)
+                .or_insert((word.id, word.mass_visible, word.pos));
+            let (best_id, best_mass, best_pos) = entry;
             if word.mass_visible > *best_mass
                 || (word.mass_visible == *best_mass && word.id < *best_id)
             {
                 *best_id = word.id;
                 *best_mass = word.mass_visible;
+                *best_pos = word.pos;
             }
         }
     }
 
     let mut items: Vec<FocusCandidate> = map
         .into_iter()
-        .map(|(component, (word_id, mass_visible))| FocusCandidate {
+        .map(|(component, (word_id, mass_visible, pos))| FocusCandidate {
             component,
             word_id,
             mass_visible,
+            distance: (pos - anchor).length(),
         })
         .collect();
 
     items.sort_by(|a, b| {
-        b.mass_visible
-            .partial_cmp(&a.mass_visible)
+        let primary = match order {
+            FocusOrder::ByMass => b.mass_visible.partial_cmp(&a.mass_visible),
+            FocusOrder::ByProximity => a.distance.partial_cmp(&b.distance),
+        };
+        primary
             .unwrap_or(Ordering::Equal)
             .then_with(|| a.word_id.cmp(&b.word_id))
             .then_with(|| a.component.cmp(&b.component))
@@ -412,7 +1433,19 @@ fn display_text(text: &str) -> String {
         .collect()
 }
 
-fn color_for(color: ColorId) -> Color {
+fn supports_truecolor() -> bool {
+    std::env::var("COLORTERM")
+        .map(|value| value.contains("truecolor") || value.contains("24bit"))
+        .unwrap_or(false)
+}
+
+// 256色パレットの 6x6x6 キューブで近似する
+fn rgb_to_ansi256(r: u8, g: u8, b: u8) -> u8 {
+    let level = |v: u8| ((v as u16 * 5 + 127) / 255) as u8;
+    16 + 36 * level(r) + 6 * level(g) + level(b)
+}
+
+fn color_for(color: ColorId, truecolor: bool) -> Color {
     match color {
         ColorId::White => Color::White,
         ColorId::Cyan => Color::Cyan,
@@ -422,6 +1455,960 @@ fn color_for(color: ColorId) -> Color {
         ColorId::Red => Color::Red,
         ColorId::Gray => Color::DarkGray,
         ColorId::Trail => Color::DarkGray,
+        ColorId::Prediction => Color::Blue,
         ColorId::Spark => Color::LightYellow,
+        ColorId::Highlight => Color::LightGreen,
+        ColorId::Shade => Color::DarkGray,
+        ColorId::MergeWarning => Color::LightRed,
+        ColorId::Rgb(r, g, b) if truecolor => Color::Rgb(r, g, b),
+        ColorId::Rgb(r, g, b) => Color::Indexed(rgb_to_ansi256(r, g, b)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Word, WordFlags, TRAIL_LEN};
+
+    mod rgb_to_ansi256_fn {
+        use super::*;
+
+        #[test]
+        fn maps_corners_of_color_cube() {
+            assert_eq!(rgb_to_ansi256(0, 0, 0), 16);
+            assert_eq!(rgb_to_ansi256(255, 255, 255), 231);
+            assert_eq!(rgb_to_ansi256(255, 0, 0), 196);
+        }
+    }
+
+    mod focus_order {
+        use super::*;
+
+        // 質量順と距離順が逆になるよう、重い語ほど原点から遠くに置く
+        fn world_with_line() -> World {
+            let mut world = World::new_with_seed(1);
+            world.words.clear();
+            for (i, (text, x, mass)) in
+                [("near", 1.0, 2.0), ("mid", 10.0, 5.0), ("far", 30.0, 9.0)].iter().enumerate()
+            {
+                let pos = Vec2::new(*x, 0.0);
+                world.words.push(Word {
+                    id: i as WordId + 100,
+                    text: text.to_string(),
+                    pos,
+                    vel: Vec2::ZERO,
+                    radius: 1.0,
+                    mass_total: *mass,
+                    mass_visible: *mass,
+                    mass_dust: 0.0,
+                    flags: WordFlags { can_split: false, inert: false },
+                    trail: [pos; TRAIL_LEN],
+                    trail_head: 0,
+                    trail_len: 1,
+                    age: 0.0,
+                    merge_count: 0,
+                    split_count: 0,
+                    gravity_mass_scale: 1.0,
+                    inertia_mass_scale: 1.0,
+                });
+            }
+            world
+        }
+
+        fn tour(state: &mut UiState, world: &World) -> Vec<String> {
+            let mut visited = Vec::new();
+            for _ in 0..3 {
+                let candidates = build_focus_candidates_from_world(
+                    world,
+                    state.focus_order,
+                    state.proximity_anchor,
+                );
+                state.advance_focus(&candidates);
+                visited.push(state.focus_component.clone().unwrap());
+            }
+            visited
+        }
+
+        #[test]
+        fn by_mass_visits_heaviest_first() {
+            let world = world_with_line();
+            let mut state = UiState::new();
+            assert_eq!(tour(&mut state, &world), ["far", "mid", "near"]);
+        }
+
+        #[test]
+        fn toggling_to_proximity_changes_cycle_order() {
+            let world = world_with_line();
+            let mut state = UiState::new();
+            state.toggle_focus_order();
+            assert_eq!(state.focus_order, FocusOrder::ByProximity);
+            assert_eq!(tour(&mut state, &world), ["near", "mid", "far"]);
+        }
+
+        #[test]
+        fn proximity_keeps_components_unique() {
+            let mut world = world_with_line();
+            let mut dup = world.words[2].clone();
+            dup.id = 200;
+            dup.pos = Vec2::new(-0.5, 0.0);
+            dup.mass_visible = 1.0;
+            world.words.push(dup);
+
+            let candidates =
+                build_focus_candidates_from_world(&world, FocusOrder::ByProximity, Vec2::ZERO);
+
+            assert_eq!(candidates.len(), 3);
+            let far = candidates.iter().find(|c| c.component == "far").unwrap();
+            assert_eq!(far.word_id, 102);
+        }
+
+        #[test]
+        fn subvisible_words_are_excluded() {
+            let mut world = world_with_line();
+            world.words[0].mass_visible = config::MIN_VISIBLE_MASS * 0.5;
+
+            let candidates =
+                build_focus_candidates_from_world(&world, FocusOrder::ByProximity, Vec2::ZERO);
+
+            assert!(candidates.iter().all(|c| c.component != "near"));
+        }
+    }
+
+    mod double_buffer {
+        use super::*;
+
+        fn snapshot_at(x: f32) -> Vec<WordSnapshot> {
+            let mut world = World::new_with_seed(3);
+            world.clear();
+            world.add_word("alpha".to_string(), 12.0, Vec2::new(x, 0.0));
+            world.add_word("ベータ".to_string(), 6.0, Vec2::new(x + 10.0, 4.0));
+            let mut snapshot = Vec::new();
+            world.snapshot(&mut snapshot);
+            snapshot
+        }
+
+        fn draw_into(snapshot: &[WordSnapshot], frame: &mut render::FrameBuffer) {
+            render::draw(
+                snapshot,
+                &[],
+                None,
+                &render::Camera::default(),
+                render::Viewport {
+                    width: 48,
+                    height: 12,
+                },
+                &render::DrawOptions::default(),
+                frame,
+            );
+        }
+
+        fn cells(frame: &render::FrameBuffer) -> Vec<(char, ColorId)> {
+            (0..frame.height())
+                .flat_map(|y| (0..frame.width()).map(move |x| (x, y)))
+                .map(|(x, y)| {
+                    let cell = frame.get(x, y);
+                    (cell.ch, cell.color)
+                })
+                .collect()
+        }
+
+        #[test]
+        fn swapped_front_matches_single_buffer_draw() {
+            let snapshot = snapshot_at(0.0);
+            let mut single = render::FrameBuffer::new(48, 12);
+            draw_into(&snapshot, &mut single);
+
+            let mut state = UiState::new();
+            state.ensure_viewport(48, 12);
+            draw_into(&snapshot, &mut state.back_framebuf);
+            state.swap_framebufs();
+
+            assert_eq!(cells(&state.framebuf), cells(&single));
+        }
+
+        #[test]
+        fn building_next_frame_does_not_touch_front() {
+            let mut state = UiState::new();
+            state.ensure_viewport(48, 12);
+            draw_into(&snapshot_at(0.0), &mut state.back_framebuf);
+            state.swap_framebufs();
+            let shown = cells(&state.framebuf);
+
+            draw_into(&snapshot_at(-12.0), &mut state.back_framebuf);
+            assert_eq!(cells(&state.framebuf), shown);
+            assert_ne!(cells(&state.back_framebuf), shown);
+
+            state.swap_framebufs();
+            assert_ne!(cells(&state.framebuf), shown);
+        }
+    }
+
+    mod viewport_rows {
+        use super::*;
+
+        // 使い回し導入前の行生成（毎フレーム String と Vec を新規確保していた）
+        fn legacy_lines(framebuf: &render::FrameBuffer, truecolor: bool) -> Vec<Line<'static>> {
+            let width = framebuf.width();
+            (0..framebuf.height())
+                .map(|y| {
+                    let mut spans: Vec<Span> = Vec::new();
+                    if width == 0 {
+                        return Line::from(spans);
+                    }
+                    let mut current_text = String::with_capacity(width as usize);
+                    let mut current_color = framebuf.get(0, y).color;
+                    let mut prev_wide = false;
+                    for x in 0..width {
+                        let cell = framebuf.get(x, y);
+                        let ch = if cell.ch == render::WIDE_CONTINUATION {
+                            if prev_wide {
+                                prev_wide = false;
+                                continue;
+                            }
+                            ' '
+                        } else {
+                            cell.ch
+                        };
+                        prev_wide = char_cell_width(ch) == 2;
+                        if cell.color == current_color {
+                            current_text.push(ch);
+                        } else {
+                            spans.push(Span::styled(
+                                mem::take(&mut current_text),
+                                Style::default().fg(color_for(current_color, truecolor)),
+                            ));
+                            current_text.push(ch);
+                            current_color = cell.color;
+                        }
+                    }
+                    if !current_text.is_empty() {
+                        spans.push(Span::styled(
+                            current_text,
+                            Style::default().fg(color_for(current_color, truecolor)),
+                        ));
+                    }
+                    Line::from(spans)
+                })
+                .collect()
+        }
+
+        fn busy_frame(width: u16, height: u16) -> render::FrameBuffer {
+            let mut world = World::new_with_seed(12);
+            world.add_word("全角-mixed-語".to_string(), 40.0, Vec2::new(2.0, 1.0));
+            let mut snapshot = Vec::new();
+            world.snapshot(&mut snapshot);
+            let mut frame = render::FrameBuffer::new(width, height);
+            render::draw(
+                &snapshot,
+                world.effects(),
+                None,
+                &render::Camera::default(),
+                render::Viewport { width, height },
+                &render::DrawOptions {
+                    density_shading: true,
+                    ..render::DrawOptions::default()
+                },
+                &mut frame,
+            );
+            frame
+        }
+
+        #[test]
+        fn matches_legacy_output() {
+            let frame = busy_frame(120, 40);
+            let mut rows = ViewportRows::default();
+            for truecolor in [false, true] {
+                rows.fill(&frame, truecolor);
+                assert_eq!(rows.lines, legacy_lines(&frame, truecolor));
+            }
+        }
+
+        #[test]
+        fn reuses_line_buffers_across_frames() {
+            // 行・Span の Vec と String の確保先が同じなら、作り直さずに使い回している
+            fn buffers(rows: &ViewportRows) -> Vec<(usize, Vec<usize>)> {
+                let span_ptr = |span: &Span| span.content.as_ptr() as usize;
+                let line = |line: &Line| {
+                    (line.spans.as_ptr() as usize, line.spans.iter().map(span_ptr).collect())
+                };
+                rows.lines.iter().map(line).collect()
+            }
+            let frame = busy_frame(120, 40);
+            let mut rows = ViewportRows::default();
+            rows.fill(&frame, false);
+            let lines_ptr = rows.lines.as_ptr();
+            let before = buffers(&rows);
+            rows.fill(&frame, false);
+            assert_eq!(rows.lines.as_ptr(), lines_ptr);
+            assert_eq!(buffers(&rows), before);
+        }
+
+        #[test]
+        fn renders_the_same_cells_as_a_paragraph() {
+            let frame = busy_frame(60, 20);
+            let area = Rect::new(0, 0, 62, 22);
+            let block = || Block::default().borders(Borders::ALL).title("Viewport");
+            let mut expected = ratatui::buffer::Buffer::empty(area);
+            let paragraph = Paragraph::new(legacy_lines(&frame, true)).block(block());
+            ratatui::widgets::Widget::render(paragraph, area, &mut expected);
+
+            let mut rows = ViewportRows::default();
+            rows.fill(&frame, true);
+            let mut actual = ratatui::buffer::Buffer::empty(area);
+            let inner = block().inner(area);
+            ratatui::widgets::Widget::render(block(), area, &mut actual);
+            ratatui::widgets::Widget::render(&rows, inner, &mut actual);
+            assert_eq!(actual, expected);
+        }
+
+        #[test]
+        fn shrinking_viewport_drops_extra_rows() {
+            let mut rows = ViewportRows::default();
+            rows.fill(&busy_frame(60, 20), false);
+            rows.fill(&busy_frame(30, 10), false);
+            assert_eq!(rows.lines, legacy_lines(&busy_frame(30, 10), false));
+        }
+
+        #[test]
+        fn combining_marks_follow_their_base() {
+            let mut world = World::new_with_seed(12);
+            world.clear();
+            world.add_word("か\u{3099}き".to_string(), 5.0, Vec2::ZERO);
+            let mut snapshot = Vec::new();
+            world.snapshot(&mut snapshot);
+            let mut frame = render::FrameBuffer::new(20, 5);
+            let viewport = render::Viewport { width: 20, height: 5 };
+            let camera = render::Camera::default();
+            let options = render::DrawOptions::default();
+            render::draw(&snapshot, &[], None, &camera, viewport, &options, &mut frame);
+            let mut rows = ViewportRows::default();
+            rows.fill(&frame, false);
+            let text = |line: &Line| line.spans.iter().map(|span| span.content.as_ref()).collect();
+            let rows: Vec<String> = rows.lines.iter().map(text).collect();
+            let row = rows.iter().find(|row| row.contains('か')).unwrap();
+            assert!(row.contains("か\u{3099}き"), "{row:?}");
+        }
+    }
+
+    mod histogram_bars_fn {
+        use super::*;
+
+        #[test]
+        fn peak_bin_is_full_block() {
+            assert_eq!(histogram_bars(&[0, 1, 4, 8]), " ▁▄█");
+        }
+
+        #[test]
+        fn empty_histogram_is_blank() {
+            assert_eq!(histogram_bars(&[0; 8]), " ".repeat(8));
+            assert_eq!(histogram_bars(&[]), "");
+        }
+    }
+
+    mod share {
+        use super::*;
+
+        #[test]
+        fn pasted_share_text_restores_world() {
+            let mut source = World::new_with_seed(4);
+            for _ in 0..10 {
+                source.tick(config::DT);
+            }
+            let text = crate::share::share_text(&source);
+            let mut world = World::new_with_seed(9);
+            world.clear();
+            let mut state = UiState::new();
+            state.focus_word_id = Some(12345);
+
+            state.handle_paste(&mut world, &format!("{text}\n"));
+            assert_eq!(world.stats().total_words, source.stats().total_words);
+            assert!((world.stats().total_mass - source.stats().total_mass).abs() < 1e-3);
+            assert!(state.focus_word_id.is_none());
+            assert!(state.status.starts_with("restored"));
+            assert!(state.input.is_empty());
+        }
+
+        #[test]
+        fn other_pastes_go_to_input() {
+            let mut world = World::new_with_seed(9);
+            let mut state = UiState::new();
+            state.handle_paste(&mut world, "星\n雲");
+            assert_eq!(state.input, "星雲");
+            state.handle_paste(&mut world, &"x".repeat(64));
+            assert_eq!(state.input.len(), 32);
+        }
+    }
+
+    mod render_pacer {
+        use super::*;
+
+        const SLOW: Duration = Duration::from_millis(200);
+
+        #[test]
+        fn slow_draws_stretch_interval_down_to_min_hz() {
+            let mut pacer = RenderPacer::new(true);
+            let fastest = pacer.interval();
+            pacer.record(SLOW);
+            assert!(pacer.interval() > fastest);
+            for _ in 0..100 {
+                pacer.record(SLOW);
+            }
+            assert_eq!(pacer.interval(), RenderPacer::slowest());
+        }
+
+        #[test]
+        fn fast_draws_recover_to_render_hz() {
+            let mut pacer = RenderPacer::new(true);
+            for _ in 0..100 {
+                pacer.record(SLOW);
+            }
+            let mut previous = pacer.interval();
+            for _ in 0..200 {
+                pacer.record(Duration::from_micros(100));
+                assert!(pacer.interval() <= previous);
+                previous = pacer.interval();
+            }
+            assert_eq!(pacer.interval(), RenderPacer::fastest());
+        }
+
+        #[test]
+        fn draws_between_thresholds_hold_interval() {
+            let mut pacer = RenderPacer::new(true);
+            let fastest = pacer.interval();
+            let steady = fastest.mul_f32(config::RENDER_BUSY_RATIO * 0.75);
+            for _ in 0..100 {
+                pacer.record(steady);
+            }
+            assert_eq!(pacer.interval(), fastest);
+        }
+
+        #[test]
+        fn fixed_rate_ignores_draw_time() {
+            let mut pacer = RenderPacer::new(false);
+            for _ in 0..100 {
+                pacer.record(SLOW);
+            }
+            assert_eq!(pacer.interval(), RenderPacer::fastest());
+        }
+    }
+
+    mod bell_throttle {
+        use super::*;
+
+        const INTERVAL: Duration = Duration::from_millis(500);
+
+        #[test]
+        fn silent_without_notices() {
+            let mut bell = BellThrottle::default();
+            assert!(!bell.poll(Instant::now(), INTERVAL));
+        }
+
+        #[test]
+        fn burst_rings_once_then_waits_for_the_interval() {
+            let mut bell = BellThrottle::default();
+            let start = Instant::now();
+            bell.record();
+            bell.record();
+            assert!(bell.poll(start, INTERVAL));
+            assert!(!bell.poll(start, INTERVAL));
+
+            bell.record();
+            assert!(!bell.poll(start + Duration::from_millis(100), INTERVAL));
+            bell.record();
+            assert!(!bell.poll(start + Duration::from_millis(300), INTERVAL));
+            // 待っていた通知はまとめて1回
+            assert!(bell.poll(start + INTERVAL, INTERVAL));
+            assert!(!bell.poll(start + INTERVAL * 3, INTERVAL));
+        }
+    }
+
+    mod mass_drift {
+        use super::*;
+
+        fn stats(mass_budget: f32, mass_drift: f32) -> WorldStats {
+            WorldStats {
+                mass_budget,
+                mass_drift,
+                ..WorldStats::default()
+            }
+        }
+
+        #[test]
+        fn warns_only_beyond_the_ratio() {
+            let ratio = config::MASS_DRIFT_WARN_RATIO;
+            assert!(!mass_drift_exceeded(&stats(1000.0, 0.0)));
+            assert!(!mass_drift_exceeded(&stats(1000.0, 1000.0 * ratio * 0.5)));
+            assert!(mass_drift_exceeded(&stats(1000.0, -1000.0 * ratio * 2.0)));
+            // 空のワールドでは極小の誤差で警告しない
+            assert!(!mass_drift_exceeded(&stats(0.0, ratio * 0.5)));
+        }
+    }
+
+    mod effect_usage {
+        use super::*;
+
+        #[test]
+        fn highlights_only_when_overflowing() {
+            let mut stats = WorldStats {
+                effect_usage: 0.5,
+                effect_spawns: 12,
+                ..WorldStats::default()
+            };
+            let (text, style) = effect_usage_span(&stats);
+            assert_eq!(text, " | fx: 50% +12 (overflow 0)");
+            assert_eq!(style, Style::default());
+
+            stats.effect_overflows = 3;
+            let (text, style) = effect_usage_span(&stats);
+            assert!(text.ends_with("(overflow 3)"));
+            assert_eq!(style.fg, Some(Color::LightRed));
+        }
+    }
+
+    mod telemetry {
+        use super::*;
+
+        #[test]
+        fn first_sample_is_immediate_then_waits_for_the_interval() {
+            let interval = Duration::from_millis(1000);
+            let mut telemetry = Telemetry::new(Box::new(io::sink()), interval);
+            let start = Instant::now();
+            assert!(telemetry.due(start));
+            assert!(!telemetry.due(start + Duration::from_millis(999)));
+            assert!(telemetry.due(start + interval));
+        }
+
+        #[test]
+        fn line_is_a_single_json_object() {
+            let stats = WorldStats {
+                tick: 120,
+                visible_count: 3,
+                total_mass: 12.5,
+                merges: 2,
+                splits: 1,
+                ..WorldStats::default()
+            };
+            let line = telemetry_json(&stats).to_string();
+            assert!(!line.contains('\n'));
+            let parsed: serde_json::Value = serde_json::from_str(&line).unwrap();
+            assert_eq!(parsed["tick"], 120);
+            assert_eq!(parsed["visible_words"], 3);
+            assert_eq!(parsed["total_mass"], 12.5);
+            assert_eq!(parsed["merges"], 2);
+            assert_eq!(parsed["splits"], 1);
+        }
+    }
+
+    mod spawn_mode {
+        use super::*;
+
+        #[test]
+        fn cycles_through_all_modes() {
+            let mut state = UiState::new();
+            assert_eq!(state.spawn_mode, config::SPAWN_MODE);
+            state.spawn_mode = SpawnMode::AtPoint;
+            state.cycle_spawn_mode();
+            assert_eq!(state.spawn_mode, SpawnMode::RandomInView);
+            state.cycle_spawn_mode();
+            assert_eq!(state.spawn_mode, SpawnMode::RandomInWorld);
+            state.cycle_spawn_mode();
+            assert_eq!(state.spawn_mode, SpawnMode::AtPoint);
+        }
+
+        #[test]
+        fn launch_velocity_follows_angle_and_speed() {
+            let mut state = UiState::new();
+            assert!(state.launch_velocity().is_none());
+            assert_eq!(state.launch_label(), "random");
+
+            state.adjust_launch_speed(3.0);
+            state.rotate_launch(90.0);
+            let vel = state.launch_velocity().unwrap();
+            // 90° は画面の上向き（y が負）
+            assert!(vel.x.abs() < 1e-4 && (vel.y + 3.0).abs() < 1e-4);
+            assert_eq!(state.launch_label(), "90° 3.0");
+
+            state.rotate_launch(-180.0);
+            assert!((state.launch_angle_deg - 270.0).abs() < 1e-4);
+            state.adjust_launch_speed(-10.0);
+            assert!(state.launch_velocity().is_none());
+            state.adjust_launch_speed(1.0e6);
+            assert_eq!(state.launch_speed, config::LAUNCH_SPEED_MAX);
+        }
+
+        #[test]
+        fn view_half_extent_follows_viewport_and_zoom() {
+            let mut state = UiState::new();
+            state.ensure_viewport(80, 20);
+            state.camera.pos = Vec2::new(30.0, -10.0);
+            let half = state.view_half_extent();
+            assert!((half.x - 40.0).abs() < 1e-4);
+            assert!((half.y - 10.0 * config::CELL_ASPECT).abs() < 1e-4);
+
+            state.camera.zoom = 2.0;
+            let zoomed = state.view_half_extent();
+            assert!((zoomed.x - 20.0).abs() < 1e-4);
+        }
+    }
+
+    mod springs {
+        use super::*;
+
+        #[test]
+        fn second_press_connects_anchor_to_focus() {
+            let mut world = World::new_with_seed(5);
+            world.clear();
+            world.add_word("alpha".to_string(), 8.0, Vec2::new(-20.0, 0.0));
+            world.add_word("beta".to_string(), 8.0, Vec2::new(20.0, 0.0));
+            let mut snapshot = Vec::new();
+            world.snapshot(&mut snapshot);
+            let (a, b) = (snapshot[0].id, snapshot[1].id);
+            let mut state = UiState::new();
+
+            state.toggle_spring(&mut world);
+            assert!(state.spring_anchor.is_none());
+            state.focus_word_id = Some(a);
+            state.toggle_spring(&mut world);
+            assert_eq!(state.spring_anchor, Some(a));
+            state.toggle_spring(&mut world);
+            assert!(state.spring_anchor.is_none() && world.springs().is_empty());
+
+            state.toggle_spring(&mut world);
+            state.focus_word_id = Some(b);
+            state.toggle_spring(&mut world);
+            assert_eq!(world.springs().len(), 1);
+            state.collect_spring_lines(&world, &snapshot);
+            assert_eq!(state.spring_lines, vec![(snapshot[0].pos, snapshot[1].pos)]);
+        }
+    }
+
+    mod inert {
+        use super::*;
+
+        #[test]
+        fn toggles_inert_on_focus_word() {
+            let mut world = World::new_with_seed(5);
+            world.clear();
+            world.add_word("alpha".to_string(), 8.0, Vec2::ZERO);
+            let id = world.words[0].id;
+            let mut state = UiState::new();
+
+            state.toggle_inert(&mut world);
+            assert!(!world.is_inert(id));
+            state.focus_word_id = Some(id);
+            state.toggle_inert(&mut world);
+            assert!(world.is_inert(id));
+            assert!(state.status.ends_with("on"));
+            state.toggle_inert(&mut world);
+            assert!(!world.is_inert(id));
+        }
+    }
+
+    mod rewind {
+        use super::*;
+
+        #[test]
+        fn reports_the_restored_tick() {
+            let mut world = World::new_with_seed(5);
+            let mut state = UiState::new();
+            state.rewind(&mut world);
+            assert_eq!(state.status, "rewind: no checkpoint yet");
+
+            world.set_time_travel(10, 4);
+            for _ in 0..20 {
+                world.tick(config::DT);
+            }
+            state.rewind(&mut world);
+            assert_eq!(state.status, "rewind: tick 10 (1 left)");
+            assert_eq!(world.tick_count(), 10);
+        }
+    }
+
+    mod merge_animation {
+        use super::*;
+
+        #[test]
+        fn animations_hide_merged_word_until_they_expire() {
+            let mut world = World::new_with_seed(5);
+            world.clear();
+            world.add_word("alpha".to_string(), 8.0, Vec2::ZERO);
+            let mut sources = Vec::new();
+            world.snapshot(&mut sources);
+            let mut state = UiState::new();
+            let start = Instant::now();
+            let record = MergeRecord { merged: 7, pos: Vec2::new(3.0, 0.0), sources };
+            state.merge_anims.push((record, start));
+
+            let halfway = start + Duration::from_secs_f32(config::MERGE_ANIM_SECS * 0.5);
+            state.update_merge_animations(&mut world, &[], halfway);
+            assert_eq!(state.merge_hidden, vec![7]);
+            assert_eq!(state.merge_ghosts.len(), 1);
+            assert!(state.merge_streaks.iter().all(|&(_, target)| target == Vec2::new(3.0, 0.0)));
+
+            let done = start + Duration::from_secs_f32(config::MERGE_ANIM_SECS);
+            state.update_merge_animations(&mut world, &[], done);
+            assert!(state.merge_anims.is_empty());
+            assert!(state.merge_hidden.is_empty());
+            assert!(state.merge_ghosts.is_empty());
+        }
+    }
+
+    mod autosave {
+        use super::*;
+
+        #[test]
+        fn due_every_interval_from_start_tick() {
+            let mut autosave = Autosave::new(10, 5);
+            assert!(!autosave.due(14));
+            assert!(autosave.due(15));
+            assert!(!autosave.due(24));
+            assert!(autosave.due(27));
+            assert!(!autosave.due(36));
+            assert!(!Autosave::new(0, 0).due(1000));
+        }
+
+        #[test]
+        fn restarts_when_the_tick_goes_back() {
+            let mut autosave = Autosave::new(10, 500);
+            assert!(!autosave.due(3));
+            assert!(autosave.due(13));
+        }
+
+        #[test]
+        fn written_autosave_resumes_with_same_mass() {
+            let mut world = World::new_with_seed(8);
+            for _ in 0..20 {
+                world.tick(config::DT);
+            }
+            let path = std::env::temp_dir().join(format!("wordcosmo2-{}.json", std::process::id()));
+            let path = path.to_str().unwrap();
+            write_autosave(&world, path).unwrap();
+
+            let mut resumed = World::new_with_seed(9);
+            resume_from(&mut resumed, path).unwrap();
+            std::fs::remove_file(path).unwrap();
+            assert_eq!(resumed.tick_count(), world.tick_count());
+            assert!((resumed.stats().total_mass - world.stats().total_mass).abs() < 1e-3);
+            assert!(write_autosave(&world, "/nonexistent-dir/autosave.json").is_err());
+        }
+    }
+
+    mod mouse_drag {
+        use super::*;
+        use crossterm::event::KeyModifiers;
+
+        fn mouse(kind: MouseEventKind, column: u16, row: u16) -> MouseEvent {
+            MouseEvent {
+                kind,
+                column,
+                row,
+                modifiers: KeyModifiers::NONE,
+            }
+        }
+
+        #[test]
+        fn maps_cells_inside_the_frame_only() {
+            let mut state = UiState::new();
+            state.viewport_area = Rect::new(0, 6, 42, 22);
+            // 枠の内側 40x20 の中央付近がカメラ位置
+            let center = state.mouse_to_world(21, 17).unwrap();
+            let viewport = render::Viewport { width: 42, height: 22 };
+            let expected = state.camera.screen_to_world(20.5, 10.5, viewport);
+            assert_eq!(center, expected);
+            assert!(state.mouse_to_world(0, 10).is_none());
+            assert!(state.mouse_to_world(10, 6).is_none());
+            assert!(state.mouse_to_world(41, 10).is_none());
+        }
+
+        #[test]
+        fn press_drag_release_grabs_and_throws() {
+            let mut world = World::new_with_seed(2);
+            world.clear();
+            world.add_word("target".to_string(), 8.0, Vec2::ZERO);
+            let start = world.words[0].pos;
+            let mut state = UiState::new();
+            state.viewport_area = Rect::new(0, 0, 82, 26);
+            let viewport = render::Viewport { width: 82, height: 26 };
+            let (sx, sy) = state.camera.world_to_screen(start, viewport);
+            let (col, row) = (sx as u16 + 1, sy as u16 + 1);
+
+            let press = MouseEventKind::Down(MouseButton::Left);
+            state.handle_mouse(&mut world, mouse(press, col, row));
+            assert_eq!(world.grabbed(), Some(world.words[0].id));
+            for i in 1..=5 {
+                let kind = MouseEventKind::Drag(MouseButton::Left);
+                state.handle_mouse(&mut world, mouse(kind, col + i, row));
+                world.tick(config::DT);
+            }
+            state.handle_mouse(&mut world, mouse(MouseEventKind::Up(MouseButton::Left), col, row));
+            assert!(world.grabbed().is_none());
+            assert!(world.words[0].pos.x > start.x);
+            assert!(world.words[0].vel.x > 0.0);
+            assert!(state.status.starts_with("throw"), "{}", state.status);
+        }
+    }
+
+    mod physics_preset {
+        use super::*;
+
+        #[test]
+        fn cycles_through_presets_and_wraps() {
+            let mut world = World::new_with_seed(5);
+            let mut state = UiState::new();
+            for k in 1..=config::PHYSICS_PRESETS.len() {
+                state.cycle_physics(&mut world);
+                let expected = config::PHYSICS_PRESETS[k % config::PHYSICS_PRESETS.len()];
+                assert_eq!(world.physics(), expected);
+                assert_eq!(state.status, format!("physics: {}", expected.name));
+            }
+            assert_eq!(world.physics(), config::DEFAULT_PHYSICS);
+        }
+    }
+
+    mod key_input {
+        use super::*;
+
+        fn press(state: &mut UiState, world: &mut World, keys: &str) {
+            let mut snapshot = Vec::new();
+            for ch in keys.chars() {
+                assert!(state.handle_key(world, &mut snapshot, KeyCode::Char(ch)).unwrap());
+            }
+        }
+
+        #[test]
+        fn spawn_mode_key_only_acts_on_an_empty_line() {
+            let mut world = World::new_with_seed(5);
+            let mut state = UiState::new();
+            let mode = state.spawn_mode;
+            press(&mut state, &mut world, "p");
+            assert_ne!(state.spawn_mode, mode);
+            assert!(state.input.is_empty());
+
+            let mode = state.spawn_mode;
+            press(&mut state, &mut world, "sp");
+            assert_eq!(state.spawn_mode, mode);
+            assert_eq!(state.input, "sp");
+        }
+
+        #[test]
+        fn layer_key_only_acts_on_an_empty_line() {
+            let mut world = World::new_with_seed(5);
+            let mut state = UiState::new();
+            let order = state.layer_order;
+            press(&mut state, &mut world, "sl");
+            assert_eq!(state.layer_order, order);
+            assert_eq!(state.input, "sl");
+
+            state.input.clear();
+            press(&mut state, &mut world, "l");
+            assert_ne!(state.layer_order, order);
+        }
+
+        #[test]
+        fn vertical_key_only_acts_on_an_empty_line() {
+            let mut world = World::new_with_seed(5);
+            let mut state = UiState::new();
+            press(&mut state, &mut world, "sv");
+            assert!(!state.vertical_text);
+            assert_eq!(state.input, "sv");
+
+            state.input.clear();
+            press(&mut state, &mut world, "v");
+            assert!(state.vertical_text);
+        }
+
+        #[test]
+        fn spring_keys_only_act_on_an_empty_line() {
+            let mut world = World::new_with_seed(5);
+            let mut state = UiState::new();
+            press(&mut state, &mut world, "sbB");
+            assert_eq!(state.input, "sbB");
+            assert!(state.status.is_empty());
+
+            state.input.clear();
+            press(&mut state, &mut world, "B");
+            assert_eq!(state.status, "springs cleared");
+        }
+
+        #[test]
+        fn zone_commands_can_be_typed() {
+            let mut world = World::new_with_seed(5);
+            let mut state = UiState::new();
+            let mut snapshot = Vec::new();
+            let order = state.focus_order;
+            press(&mut state, &mut world, "zone");
+            assert_eq!(state.focus_order, order);
+            assert!(state.handle_key(&mut world, &mut snapshot, KeyCode::Enter).unwrap());
+            assert_eq!(world.friction_zones().len(), 1);
+
+            press(&mut state, &mut world, "/zones");
+            assert!(state.handle_key(&mut world, &mut snapshot, KeyCode::Enter).unwrap());
+            assert!(world.friction_zones().is_empty());
+        }
+
+        #[test]
+        fn temperature_keys_only_act_on_an_empty_line() {
+            let mut world = World::new_with_seed(5);
+            let mut state = UiState::new();
+            let temperature = world.temperature();
+            press(&mut state, &mut world, "t(x)");
+            assert_eq!(world.temperature(), temperature);
+            assert_eq!(state.input, "t(x)");
+
+            state.input.clear();
+            press(&mut state, &mut world, ")");
+            assert!(world.temperature() > temperature);
+        }
+
+        #[test]
+        fn clear_command_can_be_typed() {
+            let mut world = World::new_with_seed(5);
+            let mut state = UiState::new();
+            let mut snapshot = Vec::new();
+            press(&mut state, &mut world, "/clear!");
+            assert_eq!(state.input, "/clear!");
+            assert!(state.handle_key(&mut world, &mut snapshot, KeyCode::Enter).unwrap());
+            assert_eq!(world.stats().total_words, 0);
+            assert!(state.status.is_empty());
+        }
+
+        #[test]
+        fn rewind_key_only_acts_on_an_empty_line() {
+            let mut world = World::new_with_seed(5);
+            let mut state = UiState::new();
+            world.set_time_travel(10, 4);
+            for _ in 0..20 {
+                world.tick(config::DT);
+            }
+            press(&mut state, &mut world, "sun");
+            assert_eq!(state.input, "sun");
+            assert_eq!(world.tick_count(), 20);
+
+            state.input.clear();
+            press(&mut state, &mut world, "u");
+            assert_eq!(world.tick_count(), 10);
+        }
+
+        #[test]
+        fn physics_key_only_acts_on_an_empty_line() {
+            let mut world = World::new_with_seed(5);
+            let mut state = UiState::new();
+            press(&mut state, &mut world, "sP");
+            assert_eq!(state.input, "sP");
+            assert_eq!(world.physics(), config::DEFAULT_PHYSICS);
+
+            state.input.clear();
+            press(&mut state, &mut world, "P");
+            assert_ne!(world.physics(), config::DEFAULT_PHYSICS);
+        }
+
+        #[test]
+        fn focus_key_turns_on_follow_mode() {
+            let mut world = World::new_with_seed(5);
+            let mut state = UiState::new();
+            assert!(!state.following_focus());
+            press(&mut state, &mut world, "f");
+            assert!(state.following_focus());
+        }
     }
 }